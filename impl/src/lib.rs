@@ -0,0 +1,768 @@
+//! The procedural macro backend of [`flags-macro`], used when its
+//! `proc-macro` feature is enabled. This crate is an implementation detail of
+//! `flags-macro` and should not be used directly.
+//!
+//! The input of `flags!`, `set_array!`, `const_flags!`, `update_flags!`, and
+//! the `*_strict` variants is parsed here so that malformed input is reported
+//! with a diagnostic pointing at the exact offending token. Well-formed input
+//! is handed back to the `macro_rules!` implementation of `flags-macro`, so
+//! both backends produce the same expansion.
+//!
+//! `#[derive(DefaultSet)]`, enabled by the `derive` feature of `flags-macro`,
+//! is also defined here.
+//!
+//! [`flags-macro`]: https://crates.io/crates/flags-macro
+extern crate proc_macro;
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use std::iter::FromIterator;
+
+/// Validates the input of a `flags-macro` macro and forwards it to
+/// `$crate::__parse_path!`.
+///
+/// The input has the form `($crate) kind (callback) [args] input...`, where
+/// `kind` is the name of the public macro being expanded and the rest is
+/// passed to `__parse_path!` verbatim.
+#[proc_macro]
+pub fn check_items(input: TokenStream) -> TokenStream {
+    let mut tokens = input.into_iter();
+    let krate = expect_group(tokens.next(), Delimiter::Parenthesis);
+    let kind = match tokens.next() {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        _ => panic!("malformed internal input: expected the macro kind"),
+    };
+    let callback = expect_group(tokens.next(), Delimiter::Parenthesis);
+    let args = expect_group(tokens.next(), Delimiter::Bracket);
+    let input: Vec<TokenTree> = tokens.collect();
+
+    match check(&kind, &input) {
+        Ok(checked) => expand(krate, &kind, callback, args, input, &checked),
+        Err(e) => e.into_compile_error(),
+    }
+}
+
+fn expect_group(token: Option<TokenTree>, delimiter: Delimiter) -> Group {
+    match token {
+        Some(TokenTree::Group(ref group)) if group.delimiter() == delimiter => group.clone(),
+        _ => panic!("malformed internal input: expected a group"),
+    }
+}
+
+/// A diagnostic reported as `compile_error!`.
+struct Error {
+    span: Span,
+    message: String,
+}
+
+impl Error {
+    fn new(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+        }
+    }
+
+    fn into_compile_error(self) -> TokenStream {
+        let mut message = Literal::string(&self.message);
+        message.set_span(self.span);
+        let mut bang = Punct::new('!', Spacing::Alone);
+        bang.set_span(self.span);
+        let mut body = Group::new(Delimiter::Brace, TokenTree::Literal(message).into());
+        body.set_span(self.span);
+        TokenStream::from_iter(vec![
+            TokenTree::Ident(Ident::new("compile_error", self.span)),
+            TokenTree::Punct(bang),
+            TokenTree::Group(body),
+        ])
+    }
+}
+
+/// An item that appeared more than once in an item list.
+struct Duplicate {
+    span: Span,
+    name: String,
+}
+
+/// The result of a successful check.
+struct Checked {
+    duplicates: Vec<Duplicate>,
+    /// Whether some items share a name and at least one of them has
+    /// attributes, in which case only `cfg` can tell if they are duplicates.
+    cfg_dependent: bool,
+}
+
+/// Checks `path::ty::{...} rest...`.
+fn check(kind: &str, input: &[TokenTree]) -> Result<Checked, Error> {
+    let items_pos = input
+        .iter()
+        .position(|t| is_group(t, Delimiter::Brace))
+        .ok_or_else(|| {
+            let span = input.last().map_or_else(Span::call_site, TokenTree::span);
+            Error::new(span, format!("expected `{}![path::{{...}}]`", kind))
+        })?;
+    let items = match input[items_pos] {
+        TokenTree::Group(ref group) => group,
+        _ => unreachable!(),
+    };
+
+    let prefix = &input[..items_pos];
+    let rest = &input[items_pos + 1..];
+
+    // `{path1::Item1, ...} as ty`
+    let mixed = prefix.is_empty()
+        && kind != "const_flags"
+        && kind != "update_flags"
+        && rest.first().is_some_and(|t| t.to_string() == "as");
+    if mixed {
+        if rest.len() == 1 {
+            return Err(Error::new(rest[0].span(), "expected a type after `as`"));
+        }
+        let checked = check_item_list(kind, items.stream(), true)?;
+        check_duplicates(kind, &checked)?;
+        return Ok(checked);
+    }
+
+    let path_len = if prefix.len() >= 2
+        && is_punct(&prefix[prefix.len() - 2], ':')
+        && is_punct(&prefix[prefix.len() - 1], ':')
+    {
+        prefix.len() - 2
+    } else if prefix.is_empty() {
+        0
+    } else {
+        return Err(Error::new(items.span(), "expected `::` before `{`"));
+    };
+    if path_len == 0 {
+        return Err(Error::new(
+            items.span(),
+            "the path prefix (`A::` of `A::{...}`) must not be empty",
+        ));
+    }
+
+    match (kind, rest.first()) {
+        (_, None) => {}
+        ("const_flags", Some(TokenTree::Ident(ref ident)))
+        | ("set_array", Some(TokenTree::Ident(ref ident)))
+        | ("set_array_strict", Some(TokenTree::Ident(ref ident)))
+            if ident.to_string() == "as" => {}
+        ("const_flags", Some(TokenTree::Ident(ref ident)))
+            if ident.to_string() == "retain" && rest.len() == 1 => {}
+        // A set expression (`A::{...} & !A::{...}`). The other operands are
+        // checked when they are expanded.
+        ("flags", Some(token)) | ("flags_strict", Some(token)) if is_set_operator(rest) => {
+            if rest.len() == 1 {
+                return Err(Error::new(
+                    token.span(),
+                    format!("expected an operand after `{}`", token),
+                ));
+            }
+        }
+        (_, Some(token)) => {
+            return Err(Error::new(
+                token.span(),
+                format!("unexpected `{}` after the item list", token),
+            ));
+        }
+    }
+
+    let checked = check_item_list(kind, items.stream(), false)?;
+    check_duplicates(kind, &checked)?;
+    Ok(checked)
+}
+
+/// Rejects duplicate items for the `*_strict` variants.
+fn check_duplicates(kind: &str, checked: &Checked) -> Result<(), Error> {
+    if kind.ends_with("_strict") {
+        if let Some(duplicate) = checked.duplicates.first() {
+            return Err(Error::new(
+                duplicate.span,
+                format!("duplicate item `{}`", duplicate.name),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Finds the end of a condition or a spread expression starting at `i`, which
+/// extends to the next `|` or `,`. A `||` is part of the expression.
+fn skip_expr(items: &[TokenTree], mut i: usize) -> usize {
+    while i < items.len() && !is_punct(&items[i], ',') {
+        if is_punct(&items[i], '|') {
+            match items.get(i + 1) {
+                Some(next) if is_joint(&items[i]) && is_punct(next, '|') => i += 1,
+                _ => break,
+            }
+        }
+        i += 1;
+    }
+    i
+}
+
+/// Skips the outer attributes (`#[...]`) starting at `i`. Returns the index of
+/// the first token following them and whether there were any.
+fn skip_attrs(kind: &str, items: &[TokenTree], mut i: usize) -> Result<(usize, bool), Error> {
+    let start = i;
+    while i < items.len() && is_punct(&items[i], '#') {
+        if kind == "const_flags" {
+            return Err(Error::new(
+                items[i].span(),
+                "attributes on items are not supported by `const_flags!` because its items \
+                 are folded by `|`",
+            ));
+        }
+        match items.get(i + 1) {
+            Some(t) if is_group(t, Delimiter::Bracket) => {}
+            _ => return Err(Error::new(items[i].span(), "expected `[` after `#`")),
+        }
+        i += 2;
+    }
+    Ok((i, i != start))
+}
+
+/// Checks the contents of `{...}`. `mixed` indicates whether the items have
+/// full paths (`{...} as ty`).
+fn check_item_list(kind: &str, items: TokenStream, mixed: bool) -> Result<Checked, Error> {
+    let items: Vec<TokenTree> = items.into_iter().collect();
+    let (first, _) = skip_attrs(kind, &items, 0)?;
+
+    if mixed {
+        if let Some(t) = items
+            .get(first)
+            .filter(|t| is_punct(t, '*') || is_punct(t, '!'))
+        {
+            return Err(Error::new(
+                t.span(),
+                format!("`{}` requires a common path prefix (`A::{{...}}`)", t),
+            ));
+        }
+    }
+
+    // Exclusion (`*` and `!`) is only supported by `flags!`
+    if kind != "flags" && kind != "flags_strict" {
+        if let Some(t) = items
+            .get(first)
+            .filter(|t| is_punct(t, '*') || is_punct(t, '!'))
+        {
+            return Err(Error::new(
+                t.span(),
+                format!("`{}` is only supported by `flags!`", t),
+            ));
+        }
+    }
+
+    let mut names = Names {
+        names: Vec::new(),
+        checked: Checked {
+            duplicates: Vec::new(),
+            cfg_dependent: false,
+        },
+    };
+
+    if items.first().is_some_and(|t| is_punct(t, '*')) {
+        // `* - Item1 - ... - ItemN`
+        let mut i = 1;
+        while i < items.len() {
+            if !is_punct(&items[i], '-') {
+                return Err(Error::new(
+                    items[i].span(),
+                    format!("expected `-`, found `{}`", items[i]),
+                ));
+            }
+            let (next, has_attrs) = skip_attrs(kind, &items, i + 1)?;
+            i = next;
+            names.record("", expect_item(items.get(i), &items[i - 1])?, has_attrs);
+            i += 1;
+        }
+        return Ok(names.checked);
+    }
+
+    let negated = items.get(first).is_some_and(|t| is_punct(t, '!'));
+    check_list(kind, &items, negated, mixed, false, "", &mut names)?;
+
+    Ok(names.checked)
+}
+
+/// The names of the items seen so far.
+struct Names {
+    /// The path of each item (relative to the common prefix) and whether it
+    /// has attributes
+    names: Vec<(String, bool)>,
+    checked: Checked,
+}
+
+impl Names {
+    /// Records an item `ident` following the path `prefix` (`inner::` for
+    /// `inner::Item`).
+    fn record(&mut self, prefix: &str, ident: &Ident, has_attrs: bool) {
+        let name = format!("{}{}", prefix, ident);
+        let same_name = self.names.iter().filter(|(n, _)| *n == name);
+        if !has_attrs && same_name.clone().any(|&(_, a)| !a) {
+            self.checked.duplicates.push(Duplicate {
+                span: ident.span(),
+                name,
+            });
+        } else {
+            if same_name.count() > 0 {
+                self.checked.cfg_dependent = true;
+            }
+            self.names.push((name, has_attrs));
+        }
+    }
+}
+
+/// Checks a list of items separated by `|` or `,`. `in_attrs` indicates whether
+/// the list is nested in a group having attributes (`#[...] path::{...}`), and
+/// `prefix` is the path of the group (`path::`).
+fn check_list(
+    kind: &str,
+    items: &[TokenTree],
+    negated: bool,
+    mixed: bool,
+    in_attrs: bool,
+    prefix: &str,
+    names: &mut Names,
+) -> Result<(), Error> {
+    let mut i = 0;
+
+    while i < items.len() {
+        let attrs_start = i;
+        let (next, has_attrs) = skip_attrs(kind, items, i)?;
+        i = next;
+        if i == items.len() {
+            return Err(Error::new(
+                items[i - 1].span(),
+                "expected an item after the attributes",
+            ));
+        }
+
+        // `..expr`
+        if is_punct(&items[i], '.') && items.get(i + 1).is_some_and(|t| is_punct(t, '.')) {
+            if mixed {
+                return Err(Error::new(
+                    items[i].span(),
+                    "`..` requires a common path prefix (`A::{...}`)",
+                ));
+            }
+            if kind != "flags" && kind != "flags_strict" {
+                return Err(Error::new(
+                    items[i].span(),
+                    format!(
+                        "`..` is only supported by `flags!`{}",
+                        if kind.starts_with("set_array") {
+                            " because an array can't be spread at compile time"
+                        } else {
+                            ""
+                        }
+                    ),
+                ));
+            }
+            if negated {
+                return Err(Error::new(
+                    items[i].span(),
+                    "`..` cannot be combined with negated items",
+                ));
+            }
+            if has_attrs {
+                return Err(Error::new(
+                    items[attrs_start].span(),
+                    "attributes can't be applied to `..`",
+                ));
+            }
+            let dots = i;
+            i += 2;
+            i = skip_expr(items, i);
+            if i == dots + 2 {
+                return Err(Error::new(
+                    items[dots].span(),
+                    "expected an expression after `..`",
+                ));
+            }
+            if i < items.len() {
+                i += 1;
+            }
+            continue;
+        }
+
+        // `bit(n)` and `bits(expr)`
+        if let (TokenTree::Ident(ident), Some(TokenTree::Group(args))) =
+            (&items[i], items.get(i + 1))
+        {
+            let name = ident.to_string();
+            if (name == "bit" || name == "bits") && args.delimiter() == Delimiter::Parenthesis {
+                if mixed {
+                    return Err(Error::new(
+                        ident.span(),
+                        format!(
+                            "`{}(...)` requires a common path prefix (`A::{{...}}`)",
+                            name
+                        ),
+                    ));
+                }
+                if kind != "flags" && kind != "flags_strict" {
+                    return Err(Error::new(
+                        ident.span(),
+                        format!("`{}(...)` is only supported by `flags!`", name),
+                    ));
+                }
+                if negated {
+                    return Err(Error::new(
+                        ident.span(),
+                        format!("`{}(...)` cannot be combined with negated items", name),
+                    ));
+                }
+                if has_attrs {
+                    return Err(Error::new(
+                        items[attrs_start].span(),
+                        format!("attributes can't be applied to `{}(...)`", name),
+                    ));
+                }
+                if args.stream().is_empty() {
+                    return Err(Error::new(
+                        args.span(),
+                        if name == "bit" {
+                            "expected a bit position"
+                        } else {
+                            "expected an expression of the raw bits"
+                        },
+                    ));
+                }
+                i = skip_separator(items, i + 2)?;
+                continue;
+            }
+        }
+
+        // `+Item`, `-Item`, and `^Item`
+        let mut prefixed = 0;
+        if kind == "update_flags" {
+            if !(is_punct(&items[i], '+') || is_punct(&items[i], '-') || is_punct(&items[i], '^')) {
+                return Err(Error::new(
+                    items[i].span(),
+                    format!("expected `+`, `-`, or `^` before `{}`", items[i]),
+                ));
+            }
+            prefixed = 1;
+            i += 1;
+        }
+
+        if items.get(i).is_some_and(|t| is_punct(t, '!')) != negated {
+            return Err(Error::new(
+                items[i].span(),
+                "negated and non-negated items cannot be mixed",
+            ));
+        }
+        if negated {
+            i += 1;
+            prefixed = 1;
+        }
+        // `::path::Item`
+        let mut path = prefix.to_owned();
+        if mixed
+            && items.get(i).is_some_and(|t| is_punct(t, ':'))
+            && items.get(i + 1).is_some_and(|t| is_punct(t, ':'))
+        {
+            path.push_str("::");
+            i += 2;
+        }
+        let mut name = Some(expect_item(items.get(i), &items[i - prefixed])?);
+        i += 1;
+
+        // `path::Item` and `path::{...}`
+        while items.get(i).is_some_and(|t| is_punct(t, ':')) {
+            if !mixed && !kind.starts_with("set_array") {
+                return Err(Error::new(
+                    items[i].span(),
+                    "nested paths are only supported by `set_array!`",
+                ));
+            }
+            if !items.get(i + 1).is_some_and(|t| is_punct(t, ':')) {
+                return Err(Error::new(items[i].span(), "expected `::`"));
+            }
+            i += 2;
+            if let Some(segment) = name {
+                path = format!("{}{}::", path, segment);
+            }
+            match items.get(i) {
+                Some(TokenTree::Group(ref group)) if group.delimiter() == Delimiter::Brace => {
+                    let inner: Vec<TokenTree> = group.stream().into_iter().collect();
+                    check_list(
+                        kind,
+                        &inner,
+                        false,
+                        mixed,
+                        in_attrs || has_attrs,
+                        &path,
+                        names,
+                    )?;
+                    name = None;
+                    i += 1;
+                    break;
+                }
+                token => name = Some(expect_item(token, &items[i - 1])?),
+            }
+            i += 1;
+        }
+        if let Some(name) = name {
+            names.record(&path, name, in_attrs || has_attrs);
+        }
+
+        // `Item if cond`
+        if let Some(TokenTree::Ident(ref ident)) = items.get(i) {
+            if ident.to_string() == "if" {
+                if mixed {
+                    return Err(Error::new(
+                        ident.span(),
+                        "conditional items require a common path prefix (`A::{...}`)",
+                    ));
+                }
+                if kind != "flags" && kind != "flags_strict" {
+                    return Err(Error::new(
+                        ident.span(),
+                        "conditional items are only supported by `flags!`",
+                    ));
+                }
+                if negated {
+                    return Err(Error::new(
+                        ident.span(),
+                        "conditional items cannot be negated",
+                    ));
+                }
+                i += 1;
+                let cond_start = i;
+                i = skip_expr(items, i);
+                if i == cond_start {
+                    return Err(Error::new(ident.span(), "expected a condition after `if`"));
+                }
+            }
+        }
+
+        i = skip_separator(items, i)?;
+    }
+
+    Ok(())
+}
+
+/// Expects `|`, `,`, or the end of the list at `items[i]` and returns the
+/// index of the next item.
+fn skip_separator(items: &[TokenTree], i: usize) -> Result<usize, Error> {
+    match items.get(i) {
+        None => Ok(i),
+        Some(t) if is_punct(t, '|') || is_punct(t, ',') => Ok(i + 1),
+        Some(TokenTree::Ident(ref ident)) => Err(Error::new(
+            ident.span(),
+            format!("expected `|` or `,` before `{}`", ident),
+        )),
+        Some(t) => Err(Error::new(
+            t.span(),
+            format!("expected `|` or `,`, found `{}`", t),
+        )),
+    }
+}
+
+/// Expects an item name. `prev` is the preceding token, which is used to
+/// locate the error if the item list ends prematurely.
+fn expect_item<'a>(token: Option<&'a TokenTree>, prev: &TokenTree) -> Result<&'a Ident, Error> {
+    match token {
+        Some(TokenTree::Ident(ref ident)) => Ok(ident),
+        Some(t) => Err(Error::new(
+            t.span(),
+            format!("expected an item name, found `{}`", t),
+        )),
+        None => Err(Error::new(
+            prev.span(),
+            format!("expected an item name after `{}`", prev),
+        )),
+    }
+}
+
+/// Produces `$crate::__parse_path![(callback) [args] @[] input...]`. Each
+/// duplicate item is reported by referring to a deprecated constant because
+/// procedural macros can't emit warnings on stable Rust.
+///
+/// If the uniqueness of the items of a `*_strict` macro depends on `cfg`, the
+/// check is deferred to `$crate::__check_unique!`.
+fn expand(
+    krate: Group,
+    kind: &str,
+    mut callback: Group,
+    mut args: Group,
+    input: Vec<TokenTree>,
+    checked: &Checked,
+) -> TokenStream {
+    let duplicates = &checked.duplicates;
+
+    if checked.cfg_dependent && kind.ends_with("_strict") {
+        let mut check_unique: Vec<TokenTree> = krate.stream().into_iter().collect();
+        check_unique.extend("::__check_unique".parse::<TokenStream>().unwrap());
+        args = Group::new(
+            Delimiter::Bracket,
+            TokenStream::from_iter(vec![TokenTree::Group(callback), TokenTree::Group(args)]),
+        );
+        callback = Group::new(Delimiter::Parenthesis, TokenStream::from_iter(check_unique));
+    }
+
+    let mut call: Vec<TokenTree> = krate.stream().into_iter().collect();
+    call.push(TokenTree::Punct(Punct::new(':', Spacing::Joint)));
+    call.push(TokenTree::Punct(Punct::new(':', Spacing::Alone)));
+    call.push(TokenTree::Ident(Ident::new(
+        "__parse_path",
+        Span::call_site(),
+    )));
+    call.push(TokenTree::Punct(Punct::new('!', Spacing::Alone)));
+
+    let mut call_args = vec![
+        TokenTree::Group(callback),
+        TokenTree::Group(args),
+        TokenTree::Punct(Punct::new('@', Spacing::Alone)),
+        TokenTree::Group(Group::new(Delimiter::Bracket, TokenStream::new())),
+    ];
+    call_args.extend(input);
+    call.push(TokenTree::Group(Group::new(
+        Delimiter::Bracket,
+        TokenStream::from_iter(call_args),
+    )));
+
+    if duplicates.is_empty() {
+        return TokenStream::from_iter(call);
+    }
+
+    let mut body = TokenStream::new();
+    for (i, duplicate) in duplicates.iter().enumerate() {
+        let name = format!("__flags_macro_duplicate_item_{}", i);
+        body.extend(
+            format!(
+                "#[deprecated(note = \"duplicate item `{}`\")] \
+                 #[allow(non_upper_case_globals)] const {}: () = (); let _ =",
+                duplicate.name, name
+            )
+            .parse::<TokenStream>()
+            .unwrap(),
+        );
+        body.extend(TokenStream::from(TokenTree::Ident(Ident::new(
+            &name,
+            duplicate.span,
+        ))));
+        body.extend(";".parse::<TokenStream>().unwrap());
+    }
+    body.extend(call);
+
+    TokenTree::Group(Group::new(Delimiter::Brace, body)).into()
+}
+
+fn is_group(token: &TokenTree, delimiter: Delimiter) -> bool {
+    match *token {
+        TokenTree::Group(ref group) => group.delimiter() == delimiter,
+        _ => false,
+    }
+}
+
+/// Whether `tokens` starts with a binary operator of set expressions. `&&`,
+/// `||`, and compound assignments like `-=` aren't.
+fn is_set_operator(tokens: &[TokenTree]) -> bool {
+    match tokens[0] {
+        TokenTree::Punct(ref punct) if "&|^-".contains(punct.as_char()) => {
+            punct.spacing() == Spacing::Alone
+                || !(is_punct(&tokens[1], punct.as_char()) || is_punct(&tokens[1], '='))
+        }
+        _ => false,
+    }
+}
+
+fn is_punct(token: &TokenTree, ch: char) -> bool {
+    match *token {
+        TokenTree::Punct(ref punct) => punct.as_char() == ch,
+        _ => false,
+    }
+}
+
+fn is_joint(token: &TokenTree) -> bool {
+    match *token {
+        TokenTree::Punct(ref punct) => punct.spacing() == Spacing::Joint,
+        _ => false,
+    }
+}
+
+/// Implements `DefaultSet` using the set type specified by
+/// `#[default_set(...)]`.
+#[proc_macro_derive(DefaultSet, attributes(default_set))]
+pub fn derive_default_set(input: TokenStream) -> TokenStream {
+    match derive_default_set_inner(input) {
+        Ok(output) => output,
+        Err(e) => e.into_compile_error(),
+    }
+}
+
+fn derive_default_set_inner(input: TokenStream) -> Result<TokenStream, Error> {
+    let mut tokens = input.into_iter().peekable();
+    let mut set_ty = None;
+
+    // Outer attributes
+    while tokens.peek().is_some_and(|t| is_punct(t, '#')) {
+        tokens.next();
+        let attr = match tokens.next() {
+            Some(TokenTree::Group(group)) => group,
+            _ => unreachable!(),
+        };
+        let mut attr_tokens = attr.stream().into_iter();
+        match attr_tokens.next() {
+            Some(TokenTree::Ident(ref ident)) if ident.to_string() == "default_set" => {}
+            _ => continue,
+        }
+        match (attr_tokens.next(), attr_tokens.next()) {
+            (Some(TokenTree::Group(ref group)), None)
+                if group.delimiter() == Delimiter::Parenthesis && !group.stream().is_empty() =>
+            {
+                if set_ty.is_some() {
+                    return Err(Error::new(attr.span(), "duplicate `#[default_set(...)]`"));
+                }
+                set_ty = Some(group.stream());
+            }
+            _ => {
+                return Err(Error::new(
+                    attr.span(),
+                    "expected `#[default_set(SetType)]`",
+                ));
+            }
+        }
+    }
+
+    let set_ty = set_ty.ok_or_else(|| {
+        Error::new(
+            Span::call_site(),
+            "`#[derive(DefaultSet)]` requires `#[default_set(SetType)]`",
+        )
+    })?;
+
+    // Visibility, `struct`/`enum`/`union`, and the name
+    let name = loop {
+        match tokens.next() {
+            Some(TokenTree::Ident(ref ident))
+                if ["struct", "enum", "union"].contains(&&*ident.to_string()) =>
+            {
+                match tokens.next() {
+                    Some(TokenTree::Ident(name)) => break name,
+                    _ => unreachable!(),
+                }
+            }
+            Some(_) => {}
+            None => unreachable!(),
+        }
+    };
+
+    if let Some(t) = tokens.peek().filter(|t| is_punct(t, '<')) {
+        return Err(Error::new(
+            t.span(),
+            "`#[derive(DefaultSet)]` doesn't support generic types",
+        ));
+    }
+
+    let mut output: TokenStream = "impl ::flags_macro::DefaultSet for".parse().unwrap();
+    output.extend(TokenStream::from(TokenTree::Ident(name)));
+    let mut body: TokenStream = "type Set =".parse().unwrap();
+    body.extend(set_ty);
+    body.extend(";".parse::<TokenStream>().unwrap());
+    output.extend(TokenStream::from(TokenTree::Group(Group::new(
+        Delimiter::Brace,
+        body,
+    ))));
+    Ok(output)
+}
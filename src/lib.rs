@@ -1,9 +1,12 @@
 //! This crate provides a convenient macro [`flags`] for constructing bitflags.
-//! It's designed to be compatible with [`bitflags`] and [`enumflags`] but works
-//! with any bitflags-like types.
+//! It's designed to be compatible with [`bitflags`] (1.x and 2.x),
+//! [`enumflags`], [`enumflags2`], and [`flagset`] but works with any
+//! bitflags-like types.
 //!
 //! [`bitflags`]: https://crates.io/crates/bitflags
 //! [`enumflags`]: https://crates.io/crates/enumflags
+//! [`enumflags2`]: https://crates.io/crates/enumflags2
+//! [`flagset`]: https://crates.io/crates/flagset
 //!
 //! # Examples
 //!
@@ -38,11 +41,11 @@
 //!     extern crate enumflags;
 //!     # #[macro_use]
 //!     # extern crate enumflags_derive;
-//!     # fn main() {
 //!     #[derive(EnumFlags, Copy, Clone, PartialEq, Eq, Debug)]
 //!     #[repr(u8)]
 //!     pub enum Test { A = 0b0001, B = 0b0010 }
 //!
+//!     # fn main() {
 //!     let flags0 = flags![Test::{}];
 //!     let flags1 = flags![Test::{A}];
 //!     let flags2 = flags![Test::{A | B}];
@@ -52,12 +55,145 @@
 //!     assert_eq!(flags2, Test::A | Test::B);
 //!     # }
 //!
+//! [`const_flags`] does the same in a constant context:
+//!
+//!     # #[macro_use]
+//!     # extern crate flags_macro;
+//!     # #[macro_use]
+//!     # extern crate bitflags;
+//!     # bitflags! {
+//!     #     struct Test: u32 {
+//!     #         const A = 0b0001;
+//!     #         const B = 0b0010;
+//!     #     }
+//!     # }
+//!     const FLAGS: Test = const_flags![Test::{A | B}];
+//!     # fn main() {
+//!     assert_eq!(FLAGS, Test::A | Test::B);
+//!     # }
+//!
+//! [`bits`] folds plain integer constants (such as the ones of `libc`) by `|`,
+//! also in a constant context:
+//!
+//!     # #[macro_use]
+//!     # extern crate flags_macro;
+//!     mod libc {
+//!         pub const O_CREAT: i32 = 0o100;
+//!         pub const O_TRUNC: i32 = 0o1000;
+//!     }
+//!
+//!     const OFLAGS: i32 = bits![libc::{O_CREAT | O_TRUNC}];
+//!     # fn main() {
+//!     assert_eq!(OFLAGS, libc::O_CREAT | libc::O_TRUNC);
+//!     # }
+//!
+//! [`update_flags`] inserts, removes, and toggles flags of an existing set:
+//!
+//!     # #[macro_use]
+//!     # extern crate flags_macro;
+//!     # #[macro_use]
+//!     # extern crate bitflags;
+//!     # bitflags! {
+//!     #     struct Test: u32 {
+//!     #         const A = 0b0001;
+//!     #         const B = 0b0010;
+//!     #         const C = 0b0100;
+//!     #     }
+//!     # }
+//!     # fn main() {
+//!     let mut flags = flags![Test::{B}];
+//!     update_flags!(flags, Test::{+A, -B, ^C});
+//!     assert_eq!(flags, Test::A | Test::C);
+//!     # }
+//!
+//! [`flags`]: macro.flags.html
+//! [`const_flags`]: macro.const_flags.html
+//! [`bits`]: macro.bits.html
+//! [`update_flags`]: macro.update_flags.html
+//!
+//! # Cargo features
+//!
+//! - `proc-macro`: Parses the inputs of [`flags`], [`set_array`],
+//!   [`const_flags`], and [`update_flags`] using a procedural macro.
+//!   Malformed inputs such as `flags![T::{A & B}]` are reported with
+//!   diagnostics pointing at the exact offending token, and items appearing
+//!   more than once produce a warning (an error in [`flags_strict`] and
+//!   [`set_array_strict`]). Without this feature, the macros are implemented
+//!   solely by `macro_rules!`.
+//! - `alloc`: Allows [`impl_default_set`] to choose `BTreeSet` and `Vec` as
+//!   set types.
+//! - `std`: Implies `alloc`. Allows [`impl_default_set`] to choose `HashSet`
+//!   as a set type.
+//! - `derive`: Provides `#[derive(DefaultSet)]`.
+//! - `bitflags2`: Builds [`flags`] on top of `bitflags::Flags` for `bitflags`
+//!   2.x types, so that `flags![T::{}]` and `flags![T::{*}]` work for any
+//!   `T: bitflags::Flags` and exclusion respects the declared flags. Also
+//!   provides name-based lookup in the [`bitflags2`](bitflags2/index.html)
+//!   module and lets [`impl_flag_names`] implement [`FlagNames`] (used by
+//!   [`parse`] and [`display`]) for these types by `T as bitflags2`.
+//! - `serde`: Provides the [`serde`](serde/index.html) module for serializing
+//!   sets of flags as sequences of flag names.
+//!
+//! [`set_array`]: macro.set_array.html
+//! [`flags_strict`]: macro.flags_strict.html
+//! [`set_array_strict`]: macro.set_array_strict.html
+//! [`impl_default_set`]: macro.impl_default_set.html
+//! [`impl_flag_names`]: macro.impl_flag_names.html
+//! [`FlagNames`]: trait.FlagNames.html
+//! [`parse`]: fn.parse.html
+//! [`display`]: fn.display.html
+//!
 #![no_std]
-use core::{iter::FromIterator, ops::BitOr};
+#[cfg(any(feature = "proc-macro", feature = "derive"))]
+extern crate flags_macro_impl;
+
+#[cfg(feature = "derive")]
+pub use flags_macro_impl::DefaultSet;
+
+#[cfg(feature = "bitflags2")]
+extern crate bitflags2 as bitflags2_crate;
 
-/// Emits an expression of type `<E as DefaultSet>::Set` given zero or more
-/// values of type `E` defined as associated constants or enumerate items of
-/// `E`.
+#[cfg(feature = "serde")]
+extern crate serde as serde_crate;
+
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+pub extern crate alloc as __alloc;
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub extern crate std as __std;
+
+use core::{
+    fmt,
+    iter::{empty, FromIterator},
+    marker::PhantomData,
+    ops::{BitAnd, BitOr, BitXor, Not, Sub},
+};
+
+/// Emits an expression of the set type of `E` given zero or more values of
+/// type `E` defined as associated constants or enumerate items of `E`.
+///
+/// The set type and the way to build it are chosen at compile time by the
+/// first of the following traits that `E` implements:
+///
+///  1. [`DefaultSet`]: `<E as DefaultSet>::Set`, built by the methods of
+///     `DefaultSet`.
+///  2. `bitflags::Flags` (only with the `bitflags2` feature): `E` itself,
+///     built by the methods of `Flags`.
+///  3. [`BitOrDefaultSet`]: `<E as BitOrDefaultSet>::Set`, built by
+///     `FromIterator` and the bitwise operators (e.g., `bitflags` 1.x and
+///     [`enumflags`]).
+///  4. [`BitOrExtendDefaultSet`]: `<E as BitOrExtendDefaultSet>::Set`, built
+///     by `Default`, `Extend`, and the bitwise operators (e.g., `flagset`).
+///
+/// In the expansions below, `kit` stands for the value providing these
+/// methods for `E` (an internal helper of this crate), whose `set_*` methods
+/// correspond to the ones of [`DefaultSet`].
+///
+/// [`DefaultSet`]: trait.DefaultSet.html
+/// [`BitOrDefaultSet`]: trait.BitOrDefaultSet.html
+/// [`BitOrExtendDefaultSet`]: trait.BitOrExtendDefaultSet.html
+/// [`enumflags`]: https://crates.io/crates/enumflags
 ///
 /// # Examples
 ///
@@ -76,7 +212,7 @@ use core::{iter::FromIterator, ops::BitOr};
 /// expanded into:
 ///
 /// ```text
-/// <path::ty as DefaultSet>::Set::from_iter([
+/// kit.set_from_iter([
 ///     path::ty::Item1, ..., path::ty::ItemN
 /// ].iter().cloned())
 /// ```
@@ -87,130 +223,4274 @@ use core::{iter::FromIterator, ops::BitOr};
 /// path::ty::Item1 | ... | path::ty::ItemN
 /// ```
 ///
-/// # Invalid usages
+/// An empty item list (`flags![path::ty::{}]`) is expanded into
+/// `kit.set_empty()`, so it doesn't need any type annotation even if
+/// `path::ty` is a type parameter.
 ///
-/// The path prefix (denoted as `path::ty::` in Section "Syntax") must not be
-/// empty.
+/// `path::ty` is a path to a type. It may start with `::`, `crate`, `self`, or
+/// `super`, may include generic arguments (`Foo<u8>::{A}`), and may be a
+/// qualified path (`<T as Trait>::Flags::{A}`).
 ///
-#[macro_export(local_inner_macros)]
-macro_rules! flags {
-    ( $($ns:ident::)* {$($items:tt)*} ) => (
-        <__containing_type!($($ns::)*) as $crate::DefaultSet>
-            ::set_from_iter(set_array![$($ns::)*{$($items)*}].iter().cloned())
-    )
-}
-
-/// Gets `A::B` from `A::B::`.
-#[doc(hidden)]
-#[macro_export(local_inner_macros)]
-macro_rules! __containing_type {
-    () => {
-        compile_error!("The path prefix (`A::` of `flags![A::{...}]`) must not be empty.")
-    };
-    ($ns:ident::) => {$ns};
-    ($ns:ident::$($rest:ident::)*) => {$ns$(::$rest)*}
-}
-
-/// Emits an array expression containing zero or more values defined within
-/// the same namespace (or a similar language construct).
+/// ## Exclusion
 ///
-/// # Syntax
+/// ```text
+/// flags![path::ty::{*}]
+/// flags![path::ty::{* - Item1 - ... - ItemN}]
+/// flags![path::ty::{!Item1 | ... | !ItemN}]
+/// flags![path::ty::{!Item1, ..., !ItemN}]
+/// ```
+///
+/// These forms produce the set of all flags except `Item1` ... `ItemN`. They
+/// are expanded into:
 ///
 /// ```text
-/// set_array![path1::path2::{Item1 | ... | ItemN}]
-/// set_array![path1::path2::{Item1, ..., ItemN}]
+/// kit.set_complement_from_iter([
+///     path::ty::Item1, ..., path::ty::ItemN
+/// ].iter().cloned())
 /// ```
 ///
-/// `Item1` ... `ItemN` are identifiers. These expressions are expanded into:
+/// This requires the set type to implement `Not` (or `bitflags::Flags`), which
+/// is the case for [`bitflags`], [`enumflags`], and `flagset`, but not for
+/// collections.
+///
+/// [`bitflags`]: https://crates.io/crates/bitflags
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     #[macro_use]
+///     extern crate bitflags;
+///     # fn main() {
+///     bitflags! {
+///         struct Test: u32 {
+///             const A = 0b0001;
+///             const B = 0b0010;
+///             const C = 0b0100;
+///         }
+///     }
+///
+///     assert_eq!(flags![Test::{*}], Test::all());
+///     assert_eq!(flags![Test::{* - A - B}], Test::C);
+///     assert_eq!(flags![Test::{!A, !B}], Test::C);
+///     # }
+///
+/// ## Conditional items
 ///
 /// ```text
-/// [path1::path2::Item1, ..., path1::path2::ItemN]
+/// flags![path::ty::{Item1, Item2 if cond2, ..., ItemN if condN}]
 /// ```
 ///
-/// # Examples
+/// An item followed by `if cond` is included only if the `bool` expression
+/// `cond` evaluates to `true` at runtime. The unconditional items are still
+/// collected into a single array, and the conditional ones are appended to it:
+///
+/// ```text
+/// kit.set_from_iter(
+///     [path::ty::Item1].iter().cloned().chain(
+///         [(path::ty::Item2, cond2), ..., (path::ty::ItemN, condN)]
+///             .iter().cloned().filter(|x| x.1).map(|x| x.0)
+///     )
+/// )
+/// ```
+///
+/// `cond` extends to the next `|` or `,`, so a condition including them must be
+/// parenthesized.
 ///
 ///     # #[macro_use]
 ///     # extern crate flags_macro;
+///     #[macro_use]
+///     extern crate bitflags;
 ///     # fn main() {
-///     mod values {
-///         pub const A: u32 = 1;
-///         pub const B: u32 = 2;
-///         pub const C: u32 = 3;
+///     bitflags! {
+///         struct Test: u32 {
+///             const A = 0b0001;
+///             const B = 0b0010;
+///             const C = 0b0100;
+///         }
 ///     }
 ///
-///     let array0: [u32; 0] = set_array![values::{}];
-///     let array1 = set_array![values::{A}];
-///     let array2a = set_array![values::{A | B}];
-///     let array2b = set_array![values::{A, B}]; // alternative syntax
+///     let (is_admin, read_only) = (true, true);
+///     assert_eq!(flags![Test::{A, B if is_admin, C if !read_only}], Test::A | Test::B);
+///     # }
 ///
-///     assert_eq!(array0, []);
-///     assert_eq!(array1, [values::A]);
-///     assert_eq!(array2a, [values::A, values::B]);
-///     assert_eq!(array2b, [values::A, values::B]);
+/// ## Spreading sets
+///
+/// ```text
+/// flags![path::ty::{Item1, ..., ItemN, ..set1, ..., ..setM}]
+/// ```
+///
+/// An item of the form `..set` unions a set `set` (an expression of the set
+/// type) into the result. Like conditions, `set` extends to the next `|` or
+/// `,`. The union is computed by `kit.set_union`, that is, by `BitOr` for
+/// bitflags-like types and by [`DefaultSet::set_union`] (which uses `Extend`
+/// by default) for types implementing `DefaultSet`.
+///
+/// [`DefaultSet::set_union`]: trait.DefaultSet.html#method.set_union
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     #[macro_use]
+///     extern crate bitflags;
+///     # fn main() {
+///     bitflags! {
+///         struct Test: u32 {
+///             const A = 0b0001;
+///             const B = 0b0010;
+///             const C = 0b0100;
+///         }
+///     }
+///
+///     let base = flags![Test::{A}];
+///     assert_eq!(flags![Test::{B, ..base}], Test::A | Test::B);
+///     # }
+///
+/// ## Raw bits
+///
+/// ```text
+/// flags![path::ty::{Item1, ..., bit(n), bits(expr)}]
+/// ```
+///
+/// `bit(n)` and `bits(expr)` include raw bits, such as undocumented bits of
+/// hardware registers, and are unioned like spreads. They are converted by the
+/// `from_bits` method of the set type, which may return either an `Option`
+/// (e.g., `bitflags` and [`enumflags`]) or a `Result` (e.g., `enumflags2`).
+/// If it fails because the bits include ones the type doesn't declare,
+/// `flags!` panics instead of dropping them. This is the same with and without
+/// the `bitflags2` feature; a `bitflags` 2.x type can accept any bits by
+/// declaring `const _ = !0;`. Types without `from_bits`, such as `flagset`
+/// types and the ones using [`impl_default_set`], reject raw bits at compile
+/// time.
+///
+/// `n` is a `u32` and must be less than the width of the bits. This is checked
+/// at runtime, also in release builds, instead of wrapping around. A literal
+/// `expr` that doesn't fit in the bits is rejected at compile time.
+///
+/// [`impl_default_set`]: macro.impl_default_set.html
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     # #[macro_use]
+///     # extern crate bitflags2;
+///     # fn main() {
+///     bitflags! {
+///         #[derive(Clone, Copy)]
+///         struct Reg: u16 {
+///             const EN = 0x0001;
+///             const _ = !0;
+///         }
+///     }
+///
+///     assert_eq!(flags![Reg::{EN, bit(7), bits(0x300)}].bits(), 0x0381);
+///     # }
+///
+/// ## Attributes
+///
+/// ```text
+/// flags![path::ty::{Item1, #[cfg(pred2)] Item2, ..., #[cfg(predN)] !ItemN}]
+/// ```
+///
+/// Items, including negated, excluded, and conditional ones, may be preceded
+/// by outer attributes, which are applied to the corresponding array elements
+/// in the expansion. In particular, an item whose `#[cfg]` is false is
+/// removed. Spreads and raw bits can't have attributes.
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     #[macro_use]
+///     extern crate bitflags;
+///     # fn main() {
+///     bitflags! {
+///         struct Test: u32 {
+///             const A = 0b0001;
+///             const B = 0b0010;
+///         }
+///     }
+///
+///     assert_eq!(flags![Test::{A, #[cfg(any())] B}], Test::A);
+///     # }
+///
+/// ## Items without a common prefix
+///
+/// ```text
+/// flags![{path1::Item1, ..., pathN::ItemN} as ty]
+/// ```
+///
+/// Items from different namespaces sharing the element type `ty` can be
+/// written with their full paths. Like the ones of [`set_array`], they may be
+/// grouped by braces (`{io::{READ, WRITE}, timer::EXPIRED} as ty`). This form
+/// is expanded into:
+///
+/// ```text
+/// kit.set_from_iter([path1::Item1, ..., pathN::ItemN].iter().cloned())
+/// ```
+///
+/// Exclusion, conditional items, spreads, and raw bits require a common
+/// prefix.
+///
+/// [`set_array`]: macro.set_array.html
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     #[macro_use]
+///     extern crate bitflags;
+///     bitflags! {
+///         pub struct Event: u32 {
+///             const READ = 0b0001;
+///             const WRITE = 0b0010;
+///             const EXPIRED = 0b0100;
+///         }
+///     }
+///
+///     mod io {
+///         pub const READ: super::Event = super::Event::READ;
+///         pub const WRITE: super::Event = super::Event::WRITE;
+///     }
+///     mod timer {
+///         pub const EXPIRED: super::Event = super::Event::EXPIRED;
+///     }
+///
+///     # fn main() {
+///     assert_eq!(flags![{io::READ, timer::EXPIRED} as Event], Event::READ | Event::EXPIRED);
+///     assert_eq!(flags![{io::{READ, WRITE}} as Event], Event::READ | Event::WRITE);
+///     # }
+///
+/// ## Set expressions
+///
+/// ```text
+/// flags![path::ty::{...} & !(path::ty::{...} | path::ty::{...}) ^ path::ty::{...}]
+/// ```
+///
+/// Item lists (each one accepting any of the forms above except
+/// `{...} as ty`) can be combined by `|` (union), `&` (intersection), `-`
+/// (difference), `^` (symmetric difference), and `!` (complement). The
+/// operators have the same precedence as in Rust expressions and can be
+/// grouped by parentheses.
+///
+/// The operations are provided by `kit` like the other forms, that is, by
+/// `bitflags::Flags` (with the `bitflags2` feature), the bitwise operators of
+/// the set type for types implementing `BitOrDefaultSet` or
+/// `BitOrExtendDefaultSet`, and the corresponding methods of [`DefaultSet`]
+/// for types implementing it. The complement requires the set type to support it,
+/// so it's not available for collections.
+///
+/// [`DefaultSet`]: trait.DefaultSet.html
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     #[macro_use]
+///     extern crate bitflags;
+///     # fn main() {
+///     bitflags! {
+///         struct Test: u32 {
+///             const A = 0b0001;
+///             const B = 0b0010;
+///             const C = 0b0100;
+///         }
+///     }
+///
+///     assert_eq!(flags![Test::{A | B} & !Test::{B} ^ Test::{C}], Test::A | Test::C);
+///     assert_eq!(flags![Test::{*} - (Test::{A} | Test::{C})], Test::B);
 ///     # }
+///
+/// # Invalid usages
+///
+/// The path prefix (denoted as `path::ty::` in Section "Syntax") must not be
+/// empty.
+///
+/// Negated and non-negated items cannot be mixed.
+///
+/// Conditional items, spreads, and raw bits can't be combined with exclusion.
+///
 #[macro_export(local_inner_macros)]
-macro_rules! set_array {
-    ( $($ns:ident::)* {$($items:tt)*} ) => (
-        __set_array![@[] $($ns::)*{$($items)*}]
+macro_rules! flags {
+    ( ! $($tt:tt)* ) => (
+        __flags_expr![flags @top @[] {! $($tt)*}]
+    );
+
+    ( ($($inner:tt)*) $($tt:tt)* ) => (
+        __flags_expr![flags @top @[] {($($inner)*) $($tt)*}]
+    );
+
+    ( $($tt:tt)* ) => (
+        __frontend![flags ($crate::__flags) [flags] $($tt)*]
     )
 }
 
 #[doc(hidden)]
 #[macro_export(local_inner_macros)]
-macro_rules! __set_array {
-    ( @[$($out:tt)*] $($ns:ident::)* {} ) => (
-        [$($out)*]
+macro_rules! __flags {
+    ( $kind:ident @mixed ($ty:ty) {} ) => (
+        __set_kit!($ty).set_empty()
     );
 
-    ( @[$($out:tt)*] $($ns:ident::)* {$tail:ident} ) => (
-        [$($out)* $($ns::)*$tail]
+    ( $kind:ident @mixed ($ty:ty) {$($items:tt)*} ) => (
+        __set_kit!($ty).set_from_iter(__mixed_array![@[] @item[] {$($items)*}].iter().cloned())
     );
 
-    ( @[$($out:tt)*] $($ns:ident::)* {$head:ident | $($rest:tt)*} ) => (
-        __set_array![
-            @[$($out)* $($ns::)*$head,]
-            $($ns::)*{$($rest)*}
-        ]
+    ( $kind:ident ($($path:tt)*) {} ) => (
+        __set_kit!($($path)*).set_empty()
     );
 
-    ( @[$($out:tt)*] $($ns:ident::)* {$head:ident , $($rest:tt)*} ) => (
-        __set_array![
-            @[$($out)* $($ns::)*$head,]
-            $($ns::)*{$($rest)*}
-        ]
+    ( $kind:ident ($($path:tt)*) {*} ) => (
+        __set_kit!($($path)*).set_all()
+    );
+
+    ( $kind:ident ($($path:tt)*) {* $(- $(#[$attr:meta])* $items:ident)+} ) => (
+        __set_kit!($($path)*).set_complement_from_iter(
+            __set_array![@[] ($($path)*) {$($(#[$attr])* $items),+}].iter().cloned()
+        )
+    );
+
+    ( $kind:ident ($($path:tt)*) {$(#[$attr:meta])* ! $($items:tt)*} ) => (
+        __set_kit!($($path)*).set_complement_from_iter(
+            __negated_set_array![@[] ($($path)*) {$(#[$attr])* ! $($items)*}].iter().cloned()
+        )
+    );
+
+    ( $kind:ident ($($path:tt)*) {$($items:tt)*} ) => (
+        __flags_items![@[] @[] @[] ($($path)*) {$($items)*}]
+    );
+
+    // The first operand of a set expression
+    ( $kind:ident ($($path:tt)*) {$($items:tt)*} $($rest:tt)+ ) => (
+        __flags_operand![$kind @top @[] ($($path)*) {$($items)*} $($rest)+]
     )
 }
 
-/// A trait for getting the default "set" type from an "element" type.
-///
-/// This trait has a blanket implementation for bitflags-like types.
-pub trait DefaultSet: Sized {
-    type Set: FromIterator<Self>;
+/// Implements set expressions (`A::{...} & !(A::{...} | A::{...})`) of
+/// `flags!`. The operators and parentheses are copied into `@[...]` as they
+/// are, so precedence is left to the compiler, and each operand is parsed by
+/// `__flags_operand`. `@top` or `@nested` tells whether the expression is
+/// parenthesized.
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __flags_expr {
+    ( $kind:ident @top @[$($out:tt)*] {} ) => (
+        ($($out)*).into_set()
+    );
 
-    /// Construct a `Set` using `Set::from_iter`.
-    fn set_from_iter(iter: impl IntoIterator<Item = Self>) -> Self::Set {
-        Self::Set::from_iter(iter)
-    }
+    ( $kind:ident @nested @[$($out:tt)*] {} ) => (
+        $($out)*
+    );
+
+    ( $kind:ident @$pos:ident @[$($out:tt)*] {($($inner:tt)*) $($rest:tt)*} ) => (
+        __flags_expr![
+            $kind @$pos @[$($out)* (__flags_expr![$kind @nested @[] {$($inner)*}])]
+            {$($rest)*}
+        ]
+    );
+
+    ( $kind:ident @$pos:ident @[$($out:tt)*] {! $($rest:tt)*} ) => (
+        __flags_expr![$kind @$pos @[$($out)* !] {$($rest)*}]
+    );
+
+    ( $kind:ident @$pos:ident @[$($out:tt)*] {& $($rest:tt)*} ) => (
+        __flags_expr![$kind @$pos @[$($out)* &] {$($rest)*}]
+    );
+
+    ( $kind:ident @$pos:ident @[$($out:tt)*] {| $($rest:tt)*} ) => (
+        __flags_expr![$kind @$pos @[$($out)* |] {$($rest)*}]
+    );
+
+    ( $kind:ident @$pos:ident @[$($out:tt)*] {^ $($rest:tt)*} ) => (
+        __flags_expr![$kind @$pos @[$($out)* ^] {$($rest)*}]
+    );
+
+    ( $kind:ident @$pos:ident @[$($out:tt)*] {- $($rest:tt)*} ) => (
+        __flags_expr![$kind @$pos @[$($out)* -] {$($rest)*}]
+    );
+
+    ( $kind:ident @$pos:ident @[$($out:tt)*] {$($rest:tt)+} ) => (
+        __frontend![$kind ($crate::__flags_operand) [$kind @$pos @[$($out)*]] $($rest)+]
+    )
 }
 
-impl<T> DefaultSet for T
-where
-    T: BitOr,
-    <T as BitOr>::Output: FromIterator<Self>,
-{
-    type Set = <T as BitOr>::Output;
+/// Wraps an operand `(path::ty) {...}` of a set expression in `SetExpr`, whose
+/// operators are implemented by the kit of `path::ty`, and passes the tokens
+/// following it back to `__flags_expr`.
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __flags_operand {
+    ( $kind:ident @$pos:ident @[$($out:tt)*] @mixed $($rest:tt)* ) => (
+        __compile_error!("Set expressions require a common path prefix (`A::{...}`) in every operand.")
+    );
+
+    ( $kind:ident @$pos:ident @[$($out:tt)*] ($($path:tt)*) {$($items:tt)*} $($rest:tt)* ) => (
+        __flags_expr![
+            $kind @$pos
+            @[$($out)* $crate::__private::SetExpr(
+                __set_kit!($($path)*),
+                __flags![$kind ($($path)*) {$($items)*}]
+            )]
+            {$($rest)*}
+        ]
+    )
 }
 
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn trailing_separators() {
-        mod values {
-            pub const A: u32 = 1;
-            pub const B: u32 = 2;
-        }
+/// Implements `flags!` for items possibly including conditional ones
+/// (`Item if cond`) and spreads (`..expr`). The unconditional items are
+/// collected into the first `@[...]`, the conditional ones into the second
+/// `@[...]`, and the spread expressions into the third `@[...]`.
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __flags_items {
+    ( @[$($items:tt)*] @[] @[$($spreads:tt)*] ($($path:tt)*) {} ) => (
+        __flags_items![@union ($($path)*) {
+            __set_kit!($($path)*).set_from_iter(
+                __set_array![@[] ($($path)*) {$($items)*}].iter().cloned()
+            )
+        } $($spreads)*]
+    );
 
-        assert_eq!(set_array![values::{A | B |}], [values::A, values::B]);
-        assert_eq!(set_array![values::{A, B,}], [values::A, values::B]);
+    ( @[$($items:tt)*] @[$($cond_items:tt)*] @[$($spreads:tt)*] ($($path:tt)*) {} ) => (
+        __flags_items![@union ($($path)*) {
+            __set_kit!($($path)*).set_from_iter(
+                __set_array![@[] ($($path)*) {$($items)*}].iter().cloned().chain(
+                    [$($cond_items)*]
+                        .iter()
+                        .cloned()
+                        .filter(|item: &($($path)*, bool)| item.1)
+                        .map(|item| item.0)
+                )
+            )
+        } $($spreads)*]
+    );
+
+    (
+        @[$($items:tt)*] @[$($cond_items:tt)*] @[$($spreads:tt)*] ($($path:tt)*)
+        {.. $($rest:tt)*}
+    ) => (
+        __flags_items![
+            @until_sep(spread) @[]
+            @[$($items)*] @[$($cond_items)*] @[$($spreads)*] ($($path)*) {$($rest)*}
+        ]
+    );
+
+    (
+        @[$($items:tt)*] @[$($cond_items:tt)*] @[$($spreads:tt)*] ($($path:tt)*)
+        {$(#[$attr:meta])+ .. $($rest:tt)*}
+    ) => (
+        __compile_error!("Attributes can't be applied to `..`.")
+    );
+
+    // `bit(n)` and `bits(expr)` are spreads of the sets converted from the
+    // raw bits by the kit
+    (
+        @[$($items:tt)*] @[$($cond_items:tt)*] @[$($spreads:tt)*] ($($path:tt)*)
+        {bit ($($n:tt)+) $($rest:tt)*}
+    ) => (
+        __flags_items![
+            @until_sep(spread)
+            @[__flags_items![@raw_bits ($($path)*) ($crate::__private::Bit::bit($($n)+))]]
+            @[$($items)*] @[$($cond_items)*] @[$($spreads)*] ($($path)*) {$($rest)*}
+        ]
+    );
+
+    (
+        @[$($items:tt)*] @[$($cond_items:tt)*] @[$($spreads:tt)*] ($($path:tt)*)
+        {bits ($($bits:tt)+) $($rest:tt)*}
+    ) => (
+        __flags_items![
+            @until_sep(spread)
+            @[__flags_items![@raw_bits ($($path)*) ($($bits)+)]]
+            @[$($items)*] @[$($cond_items)*] @[$($spreads)*] ($($path)*) {$($rest)*}
+        ]
+    );
+
+    ( @raw_bits ($($path:tt)*) ($($bits:tt)*) ) => (
+        __set_kit!($($path)*).set_from_raw_bits(
+            $($bits)*,
+            |bits| <<$($path)* as $crate::__private::SupportsRawBits>::Set>::from_bits(bits),
+        )
+    );
+
+    (
+        @[$($items:tt)*] @[$($cond_items:tt)*] @[$($spreads:tt)*] ($($path:tt)*)
+        {$(#[$attr:meta])* $item:ident if $($rest:tt)*}
+    ) => (
+        __flags_items![
+            @until_sep(cond $(#[$attr])* $item) @[]
+            @[$($items)*] @[$($cond_items)*] @[$($spreads)*] ($($path)*) {$($rest)*}
+        ]
+    );
+
+    (
+        @[$($items:tt)*] @[$($cond_items:tt)*] @[$($spreads:tt)*] ($($path:tt)*)
+        {$(#[$attr:meta])* $item:ident}
+    ) => (
+        __flags_items![
+            @[$($items)* $(#[$attr])* $item,] @[$($cond_items)*] @[$($spreads)*] ($($path)*) {}
+        ]
+    );
+
+    (
+        @[$($items:tt)*] @[$($cond_items:tt)*] @[$($spreads:tt)*] ($($path:tt)*)
+        {$(#[$attr:meta])* $item:ident | $($rest:tt)*}
+    ) => (
+        __flags_items![
+            @[$($items)* $(#[$attr])* $item,] @[$($cond_items)*] @[$($spreads)*] ($($path)*) {$($rest)*}
+        ]
+    );
+
+    (
+        @[$($items:tt)*] @[$($cond_items:tt)*] @[$($spreads:tt)*] ($($path:tt)*)
+        {$(#[$attr:meta])* $item:ident , $($rest:tt)*}
+    ) => (
+        __flags_items![
+            @[$($items)* $(#[$attr])* $item,] @[$($cond_items)*] @[$($spreads)*] ($($path)*) {$($rest)*}
+        ]
+    );
+
+    // Collect the tokens of a condition or a spread expression, which extends
+    // to the next `|` or `,`
+    ( @until_sep($($what:tt)*) @[$($expr:tt)*] $($state:tt)* ) => (
+        __flags_items![@until_sep_inner($($what)*) @[$($expr)*] $($state)*]
+    );
+
+    (
+        @until_sep_inner($($what:tt)*) @[$($expr:tt)*]
+        @[$($items:tt)*] @[$($cond_items:tt)*] @[$($spreads:tt)*] ($($path:tt)*) {}
+    ) => (
+        __flags_items![
+            @got($($what)*) ($($expr)*)
+            @[$($items)*] @[$($cond_items)*] @[$($spreads)*] ($($path)*) {}
+        ]
+    );
+
+    (
+        @until_sep_inner($($what:tt)*) @[$($expr:tt)*]
+        @[$($items:tt)*] @[$($cond_items:tt)*] @[$($spreads:tt)*] ($($path:tt)*)
+        {| $($rest:tt)*}
+    ) => (
+        __flags_items![
+            @got($($what)*) ($($expr)*)
+            @[$($items)*] @[$($cond_items)*] @[$($spreads)*] ($($path)*) {$($rest)*}
+        ]
+    );
+
+    (
+        @until_sep_inner($($what:tt)*) @[$($expr:tt)*]
+        @[$($items:tt)*] @[$($cond_items:tt)*] @[$($spreads:tt)*] ($($path:tt)*)
+        {, $($rest:tt)*}
+    ) => (
+        __flags_items![
+            @got($($what)*) ($($expr)*)
+            @[$($items)*] @[$($cond_items)*] @[$($spreads)*] ($($path)*) {$($rest)*}
+        ]
+    );
+
+    (
+        @until_sep_inner($($what:tt)*) @[$($expr:tt)*]
+        @[$($items:tt)*] @[$($cond_items:tt)*] @[$($spreads:tt)*] ($($path:tt)*)
+        {$head:tt $($rest:tt)*}
+    ) => (
+        __flags_items![
+            @until_sep_inner($($what)*) @[$($expr)* $head]
+            @[$($items)*] @[$($cond_items)*] @[$($spreads)*] ($($path)*) {$($rest)*}
+        ]
+    );
+
+    (
+        @got(cond $(#[$attr:meta])* $item:ident) ($($cond:tt)*)
+        @[$($items:tt)*] @[$($cond_items:tt)*] @[$($spreads:tt)*] ($($path:tt)*)
+        {$($rest:tt)*}
+    ) => (
+        __flags_items![
+            @[$($items)*]
+            @[$($cond_items)* $(#[$attr])* ($($path)*::$item, $($cond)*),]
+            @[$($spreads)*]
+            ($($path)*) {$($rest)*}
+        ]
+    );
+
+    (
+        @got(spread) ($($expr:tt)*)
+        @[$($items:tt)*] @[$($cond_items:tt)*] @[$($spreads:tt)*] ($($path:tt)*)
+        {$($rest:tt)*}
+    ) => (
+        __flags_items![
+            @[$($items)*] @[$($cond_items)*] @[$($spreads)* ($($expr)*)]
+            ($($path)*) {$($rest)*}
+        ]
+    );
+
+    // Union the spread sets
+    ( @union ($($path:tt)*) {$($set:tt)*} ) => (
+        $($set)*
+    );
+
+    ( @union ($($path:tt)*) {$($set:tt)*} ($($spread:tt)*) $($rest:tt)* ) => (
+        __flags_items![@union ($($path)*) {
+            __set_kit!($($path)*).set_union($($set)*, $($spread)*)
+        } $($rest)*]
+    );
+}
+
+/// Gets a value providing the methods of `DefaultSet` for the element type
+/// `$ty`, which implements `DefaultSet`, `bitflags::Flags` (with the
+/// `bitflags2` feature), `BitOrDefaultSet`, or `BitOrExtendDefaultSet` (in the
+/// order of precedence).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __set_kit {
+    ( $ty:ty ) => {{
+        #[allow(unused_imports)]
+        use $crate::__private::{
+            SelectBitOrDefaultSet, SelectBitOrExtendDefaultSet, SelectDefaultSet, SelectFlags,
+        };
+        (&&&&$crate::__private::Select::<$ty>::new()).kit()
+    }};
+}
+
+/// Gets `A::B` from `A::B::`, `A::B::{...}`, or `{...} as A::B`.
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __containing_type {
+    ( @parsed ($($path:tt)*) {$($items:tt)*} $($rest:tt)* ) => ($($path)*);
+    ( {$($items:tt)*} as $($ty:tt)+ ) => ($($ty)+);
+    ( $($tt:tt)* ) => (
+        __parse_path![($crate::__containing_type) [@parsed] @[] $($tt)* {}]
+    )
+}
+
+/// Passes the input of the public macro `$kind` to `__parse_path`. The
+/// `*_strict` variants go through `__check_unique` first.
+#[cfg(not(feature = "proc-macro"))]
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __frontend {
+    ( flags_strict ($($cb:tt)*) [$($args:tt)*] $($tt:tt)* ) => (
+        __parse_path![($crate::__check_unique) [($($cb)*) [$($args)*]] @[] $($tt)*]
+    );
+
+    ( set_array_strict ($($cb:tt)*) [$($args:tt)*] $($tt:tt)* ) => (
+        __parse_path![($crate::__check_unique) [($($cb)*) [$($args)*]] @[] $($tt)*]
+    );
+
+    ( $kind:ident ($($cb:tt)*) [$($args:tt)*] $($tt:tt)* ) => (
+        __parse_path![($($cb)*) [$($args)*] @[] $($tt)*]
+    )
+}
+
+/// Passes `(path::ty) {...} rest...` to the macro `$cb` (preceded by `$args`)
+/// after making sure no items appear more than once. Duplicate names are
+/// reported by the compiler as duplicate enum variants. Items with nested
+/// paths (`inner::Item`), which can't be variant names, are collected into
+/// the second `@[...]` and compared by `assert_unique_paths` instead.
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __check_unique {
+    ( ($($cb:tt)*) [$($args:tt)*] @mixed ($($ty:tt)*) {$($items:tt)*} ) => (
+        __check_unique![($($cb)*) [$($args)* @mixed] ($($ty)*) {$($items)*}]
+    );
+
+    ( ($($cb:tt)*) [$($args:tt)*] ($($path:tt)*) {$($items:tt)*} $($rest:tt)* ) => (
+        __check_unique![
+            @[] @[] [$($items)*]
+            ($($cb)*) [$($args)*] ($($path)*) {$($items)*} $($rest)*
+        ]
+    );
+
+    (
+        @[$([$($names:tt)*])*] @[$([$(#[$attr:meta])* ($($seg:ident ::)*) $name:ident])*] []
+        ($($cb:tt)*) [$($args:tt)*] ($($path:tt)*) {$($items:tt)*} $($rest:tt)*
+    ) => ({
+        #[allow(dead_code, non_camel_case_types, clippy::all)]
+        enum __FlagsMacroUniqueItems { $($($names)*),* }
+
+        const _: () = $crate::__private::assert_unique_paths(&[
+            $($(#[$attr])* __concat!(
+                "duplicate item `", $(__stringify!($seg), "::",)* __stringify!($name), "`"
+            ),)*
+        ]);
+
+        $($cb)*![$($args)* ($($path)*) {$($items)*} $($rest)*]
+    });
+
+    // Skip the condition of a conditional item and spread expressions
+    ( @[$($names:tt)*] @[$($paths:tt)*] [if $($tail:tt)*] $($call:tt)* ) => (
+        __check_unique![@cond @[$($names)*] @[$($paths)*] [$($tail)*] $($call)*]
+    );
+
+    ( @[$($names:tt)*] @[$($paths:tt)*] [.. $($tail:tt)*] $($call:tt)* ) => (
+        __check_unique![@cond @[$($names)*] @[$($paths)*] [$($tail)*] $($call)*]
+    );
+
+    ( @cond @[$($names:tt)*] @[$($paths:tt)*] [] $($call:tt)* ) => (
+        __check_unique![@[$($names)*] @[$($paths)*] [] $($call)*]
+    );
+
+    ( @cond @[$($names:tt)*] @[$($paths:tt)*] [| $($tail:tt)*] $($call:tt)* ) => (
+        __check_unique![@[$($names)*] @[$($paths)*] [$($tail)*] $($call)*]
+    );
+
+    ( @cond @[$($names:tt)*] @[$($paths:tt)*] [, $($tail:tt)*] $($call:tt)* ) => (
+        __check_unique![@[$($names)*] @[$($paths)*] [$($tail)*] $($call)*]
+    );
+
+    ( @cond @[$($names:tt)*] @[$($paths:tt)*] [$other:tt $($tail:tt)*] $($call:tt)* ) => (
+        __check_unique![@cond @[$($names)*] @[$($paths)*] [$($tail)*] $($call)*]
+    );
+
+    // `bit(n)` and `bits(expr)` have no names
+    ( @[$($names:tt)*] @[$($paths:tt)*] [bit ($($n:tt)*) $($tail:tt)*] $($call:tt)* ) => (
+        __check_unique![@[$($names)*] @[$($paths)*] [$($tail)*] $($call)*]
+    );
+
+    (
+        @[$($names:tt)*] @[$($paths:tt)*] [bits ($($bits:tt)*) $($tail:tt)*]
+        $($call:tt)*
+    ) => (
+        __check_unique![@[$($names)*] @[$($paths)*] [$($tail)*] $($call)*]
+    );
+
+    // The segments of a nested path (`inner::Item`) are collected in
+    // `@seg (attributes) (segments)`. The items in a group (`inner::{...}`)
+    // are prefixed by its attributes and segments.
+    (
+        @[$($names:tt)*] @[$($paths:tt)*] [$(#[$attr:meta])* $seg:ident :: $($tail:tt)*]
+        $($call:tt)*
+    ) => (
+        __check_unique![
+            @seg ($(#[$attr])*) ($seg ::) @[$($names)*] @[$($paths)*] [$($tail)*] $($call)*
+        ]
+    );
+
+    (
+        @seg ($($attrs:tt)*) ($($segs:tt)*) @[$($names:tt)*] @[$($paths:tt)*]
+        [$seg:ident :: $($tail:tt)*] $($call:tt)*
+    ) => (
+        __check_unique![
+            @seg ($($attrs)*) ($($segs)* $seg ::) @[$($names)*] @[$($paths)*] [$($tail)*]
+            $($call)*
+        ]
+    );
+
+    (
+        @seg ($($attrs:tt)*) ($($segs:tt)*) @[$($names:tt)*] @[$($paths:tt)*]
+        [{$($inner:tt)*} $($tail:tt)*] $($call:tt)*
+    ) => (
+        __prefix_items![
+            ($crate::__check_unique) [@flattened @[$($names)*] @[$($paths)*]] [$($call)*]
+            ($($attrs)* $($segs)*) {$($inner)*} {$($tail)*}
+        ]
+    );
+
+    (
+        @seg ($($attrs:tt)*) ($($segs:tt)*) @[$($names:tt)*] @[$($paths:tt)*]
+        [$name:ident $($tail:tt)*] $($call:tt)*
+    ) => (
+        __check_unique![
+            @[$($names)*] @[$($paths)* [$($attrs)* ($($segs)*) $name]] [$($tail)*]
+            $($call)*
+        ]
+    );
+
+    (
+        @[$($names:tt)*] @[$($paths:tt)*] [$(#[$attr:meta])* {$($inner:tt)*} $($tail:tt)*]
+        $($call:tt)*
+    ) => (
+        __prefix_items![
+            ($crate::__check_unique) [@flattened @[$($names)*] @[$($paths)*]] [$($call)*]
+            ($(#[$attr])*) {$($inner)*} {$($tail)*}
+        ]
+    );
+
+    ( @flattened @[$($names:tt)*] @[$($paths:tt)*] {$($items:tt)*} $($call:tt)* ) => (
+        __check_unique![@[$($names)*] @[$($paths)*] [$($items)*] $($call)*]
+    );
+
+    // The attributes of an item are copied to the enum variant so that items
+    // removed by `#[cfg]` aren't counted
+    (
+        @[$($names:tt)*] @[$($paths:tt)*] [$(#[$attr:meta])* $(!)* $name:ident $($tail:tt)*]
+        $($call:tt)*
+    ) => (
+        __check_unique![
+            @[$($names)* [$(#[$attr])* $name]] @[$($paths)*] [$($tail)*] $($call)*
+        ]
+    );
+
+    ( @[$($names:tt)*] @[$($paths:tt)*] [$other:tt $($tail:tt)*] $($call:tt)* ) => (
+        __check_unique![@[$($names)*] @[$($paths)*] [$($tail)*] $($call)*]
+    )
+}
+
+/// Passes the input of the public macro `$kind` to `__parse_path` after
+/// validating it by the procedural macro `__check_items`.
+#[cfg(feature = "proc-macro")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __frontend {
+    ( $kind:ident ($($cb:tt)*) [$($args:tt)*] $($tt:tt)* ) => (
+        $crate::__check_items!{($crate) $kind ($($cb)*) [$($args)*] $($tt)*}
+    )
+}
+
+#[cfg(feature = "proc-macro")]
+#[doc(hidden)]
+pub use flags_macro_impl::check_items as __check_items;
+
+/// Splits `path::ty::{...} rest...` into `(path::ty) {...} rest...` and passes
+/// them to the macro `$cb`, preceded by `$args`. `{...} as ty` is passed as
+/// `@mixed (ty) {...}`.
+///
+/// Generic arguments in the path are rewritten in the turbofish form (`A<T>`
+/// becomes `A::<T>`) so that the result is valid both as a type and as a
+/// prefix of an expression path.
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __parse_path {
+    // `{path1::Item1, ...} as ty` (no common prefix)
+    ( ($($cb:tt)*) [$($args:tt)*] @[] {$($items:tt)*} as $($ty:tt)+ ) => (
+        $($cb)*![$($args)* @mixed ($($ty)+) {$($items)*}]
+    );
+
+    ( ($($cb:tt)*) [$($args:tt)*] @[] $(::)* {$($items:tt)*} $($rest:tt)* ) => (
+        __compile_error!("The path prefix (`A::` of `flags![A::{...}]`) must not be empty.")
+    );
+
+    ( ($($cb:tt)*) [$($args:tt)*] @[$($out:tt)*] :: {$($items:tt)*} $($rest:tt)* ) => (
+        $($cb)*![$($args)* ($($out)*) {$($items)*} $($rest)*]
+    );
+
+    ( ($($cb:tt)*) [$($args:tt)*] @[$($out:tt)*] $seg:ident < $($rest:tt)* ) => (
+        __parse_path![($($cb)*) [$($args)*] @[$($out)* $seg::<] $($rest)*]
+    );
+
+    ( ($($cb:tt)*) [$($args:tt)*] @[$($out:tt)*] $head:tt $($rest:tt)* ) => (
+        __parse_path![($($cb)*) [$($args)*] @[$($out)* $head] $($rest)*]
+    )
+}
+
+/// Like `__set_array`, but every item is prefixed with `!`.
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __negated_set_array {
+    ( @[$($out:tt)*] ($($path:tt)*) {} ) => (
+        [$($out)*]
+    );
+
+    ( @[$($out:tt)*] ($($path:tt)*) {$(#[$attr:meta])* ! $tail:ident} ) => (
+        [$($out)* $(#[$attr])* $($path)*::$tail]
+    );
+
+    ( @[$($out:tt)*] ($($path:tt)*) {$(#[$attr:meta])* ! $head:ident | $($rest:tt)*} ) => (
+        __negated_set_array![
+            @[$($out)* $(#[$attr])* $($path)*::$head,]
+            ($($path)*) {$($rest)*}
+        ]
+    );
+
+    ( @[$($out:tt)*] ($($path:tt)*) {$(#[$attr:meta])* ! $head:ident , $($rest:tt)*} ) => (
+        __negated_set_array![
+            @[$($out)* $(#[$attr])* $($path)*::$head,]
+            ($($path)*) {$($rest)*}
+        ]
+    );
+
+    ( @[$($out:tt)*] ($($path:tt)*) {$($rest:tt)*} ) => (
+        __compile_error!("Negated and non-negated items cannot be mixed.")
+    )
+}
+
+/// `compile_error!` reachable through `local_inner_macros`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __compile_error {
+    ($($t:tt)*) => (compile_error!($($t)*))
+}
+
+/// Emits an array expression containing zero or more values defined within
+/// the same namespace (or a similar language construct).
+///
+/// # Syntax
+///
+/// ```text
+/// set_array![path1::path2::{Item1 | ... | ItemN}]
+/// set_array![path1::path2::{Item1, ..., ItemN}]
+/// set_array![path1::path2::{Item1 | ... | ItemN} as ty]
+/// set_array![path1::path2::{Item1, ..., ItemN} as ty]
+/// ```
+///
+/// `Item1` ... `ItemN` are identifiers. These expressions are expanded into:
+///
+/// ```text
+/// [path1::path2::Item1, ..., path1::path2::ItemN]
+/// ```
+///
+/// `as ty` specifies the element type, which is useful when the array is
+/// empty and nothing else constrains its type.
+///
+/// Like [`flags`], items may be preceded by attributes such as `#[cfg(...)]`,
+/// and `set_array![{path1::Item1, ..., pathN::ItemN} as ty]` accepts items
+/// without a common prefix.
+///
+/// ## Nested paths
+///
+/// ```text
+/// set_array![path1::{inner1::Item1, inner2::{Item2, Item3}, Item4}]
+/// ```
+///
+/// Unlike [`flags`], an item may be a relative path appended to the common
+/// prefix, or a group of such paths in braces as in `use` declarations. The
+/// result is a flat array in declaration order:
+///
+/// ```text
+/// [path1::inner1::Item1, path1::inner2::Item2, path1::inner2::Item3, path1::Item4]
+/// ```
+///
+/// The attributes of a group apply to all items in it.
+///
+/// The path prefix accepts the same forms as the one of [`flags`].
+///
+/// [`flags`]: macro.flags.html
+///
+/// # Examples
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     # fn main() {
+///     mod values {
+///         pub const A: u32 = 1;
+///         pub const B: u32 = 2;
+///         pub const C: u32 = 3;
+///     }
+///
+///     let array0: [u32; 0] = set_array![values::{}];
+///     let array0b = set_array![values::{} as u32];
+///     let array1 = set_array![values::{A}];
+///     let array2a = set_array![values::{A | B}];
+///     let array2b = set_array![values::{A, B}]; // alternative syntax
+///
+///     assert_eq!(array0, []);
+///     assert_eq!(array0b, array0);
+///     assert_eq!(array1, [values::A]);
+///     assert_eq!(array2a, [values::A, values::B]);
+///     assert_eq!(array2b, [values::A, values::B]);
+///
+///     mod consts {
+///         pub mod fs {
+///             pub const O_RDONLY: u32 = 0;
+///             pub const O_CREAT: u32 = 0o100;
+///         }
+///         pub mod net {
+///             pub const SOCK_STREAM: u32 = 1;
+///         }
+///     }
+///
+///     assert_eq!(
+///         set_array![consts::{fs::{O_RDONLY, O_CREAT}, net::SOCK_STREAM}],
+///         [consts::fs::O_RDONLY, consts::fs::O_CREAT, consts::net::SOCK_STREAM]
+///     );
+///     # }
+#[macro_export(local_inner_macros)]
+macro_rules! set_array {
+    ( $($tt:tt)* ) => (
+        __frontend![set_array ($crate::__set_array) [@start] $($tt)*]
+    )
+}
+
+/// Like [`flags`], but rejects items appearing more than once.
+///
+/// Items removed by `#[cfg]` don't count, so `#[cfg(unix)] A` and
+/// `#[cfg(windows)] A` may appear in the same list. The operands of a set
+/// expression are checked separately.
+///
+/// [`flags`]: macro.flags.html
+///
+/// # Examples
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     #[macro_use]
+///     extern crate bitflags;
+///     # fn main() {
+///     bitflags! {
+///         struct Test: u32 {
+///             const A = 0b0001;
+///             const B = 0b0010;
+///         }
+///     }
+///
+///     assert_eq!(flags_strict![Test::{A | B}], Test::A | Test::B);
+///     # }
+///
+/// ```compile_fail
+/// # #[macro_use]
+/// # extern crate flags_macro;
+/// # #[macro_use]
+/// # extern crate bitflags;
+/// # fn main() {
+/// # bitflags! {
+/// #     struct Test: u32 {
+/// #         const A = 0b0001;
+/// #         const B = 0b0010;
+/// #     }
+/// # }
+/// // error: the name `A` is defined multiple times
+/// let _ = flags_strict![Test::{A | B | A}];
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! flags_strict {
+    ( ! $($tt:tt)* ) => (
+        __flags_expr![flags_strict @top @[] {! $($tt)*}]
+    );
+
+    ( ($($inner:tt)*) $($tt:tt)* ) => (
+        __flags_expr![flags_strict @top @[] {($($inner)*) $($tt)*}]
+    );
+
+    ( $($tt:tt)* ) => (
+        __frontend![flags_strict ($crate::__flags) [flags_strict] $($tt)*]
+    )
+}
+
+/// Like [`set_array`], but rejects items appearing more than once.
+///
+/// Items with nested paths are compared by their whole paths, so `fs::A`
+/// and `net::A` are different items while `fs::A` and `fs::{A}` are
+/// duplicates.
+///
+/// [`set_array`]: macro.set_array.html
+///
+/// # Examples
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     # fn main() {
+///     mod values {
+///         pub const A: u32 = 1;
+///         pub const B: u32 = 2;
+///     }
+///
+///     assert_eq!(set_array_strict![values::{A, B}], [values::A, values::B]);
+///     # }
+///
+/// ```compile_fail
+/// # #[macro_use]
+/// # extern crate flags_macro;
+/// # fn main() {
+/// # mod values {
+/// #     pub const A: u32 = 1;
+/// # }
+/// // error: the name `A` is defined multiple times
+/// let _ = set_array_strict![values::{A, A}];
+/// # }
+/// ```
+///
+/// ```compile_fail
+/// # #[macro_use]
+/// # extern crate flags_macro;
+/// # fn main() {
+/// # mod consts {
+/// #     pub mod fs {
+/// #         pub const A: u32 = 1;
+/// #     }
+/// # }
+/// // error: duplicate item `fs::A`
+/// let _ = set_array_strict![consts::{fs::A, fs::{A}}];
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! set_array_strict {
+    ( $($tt:tt)* ) => (
+        __frontend![set_array_strict ($crate::__set_array) [@start] $($tt)*]
+    )
+}
+
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __set_array {
+    ( @start @mixed ($ty:ty) {$($items:tt)*} ) => ({
+        let array = __mixed_array![@[] @item[] {$($items)*}];
+        let _: &[$ty] = &array;
+        array
+    });
+
+    ( @start ($($path:tt)*) {$($items:tt)*} as $ty:ty ) => ({
+        let array = __set_array![@[] ($($path)*) {$($items)*}];
+        let _: &[$ty] = &array;
+        array
+    });
+
+    ( @start ($($path:tt)*) {$($items:tt)*} ) => (
+        __set_array![@[] ($($path)*) {$($items)*}]
+    );
+
+    ( @[$($out:tt)*] ($($path:tt)*) {} ) => (
+        [$($out)*]
+    );
+
+    ( @[$($out:tt)*] ($($path:tt)*) {.. $($rest:tt)*} ) => (
+        __compile_error!(
+            "`..` is only supported by `flags!` because an array can't be spread at compile time."
+        )
+    );
+
+    ( @[$($out:tt)*] ($($path:tt)*) {$(#[$attr:meta])+ .. $($rest:tt)*} ) => (
+        __compile_error!("Attributes can't be applied to `..`.")
+    );
+
+    ( @[$($out:tt)*] ($($path:tt)*) {$(#[$attr:meta])* $tail:ident} ) => (
+        [$($out)* $(#[$attr])* $($path)*::$tail]
+    );
+
+    ( @[$($out:tt)*] ($($path:tt)*) {$(#[$attr:meta])* $seg:ident :: $($rest:tt)*} ) => (
+        __set_array![@nested [$(#[$attr])*] [$seg ::] @[$($out)*] ($($path)*) {$($rest)*}]
+    );
+
+    ( @[$($out:tt)*] ($($path:tt)*) {$(#[$attr:meta])* $head:ident | $($rest:tt)*} ) => (
+        __set_array![
+            @[$($out)* $(#[$attr])* $($path)*::$head,]
+            ($($path)*) {$($rest)*}
+        ]
+    );
+
+    ( @[$($out:tt)*] ($($path:tt)*) {$(#[$attr:meta])* $head:ident , $($rest:tt)*} ) => (
+        __set_array![
+            @[$($out)* $(#[$attr])* $($path)*::$head,]
+            ($($path)*) {$($rest)*}
+        ]
+    );
+
+    // An item with a relative path (`inner::Item` or `inner::{...}`). The
+    // segments are collected into the second `[...]`.
+    (
+        @nested [$($attrs:tt)*] [$($rel:tt)*] @[$($out:tt)*] ($($path:tt)*)
+        {$seg:ident :: $($rest:tt)*}
+    ) => (
+        __set_array![
+            @nested [$($attrs)*] [$($rel)* $seg ::] @[$($out)*] ($($path)*) {$($rest)*}
+        ]
+    );
+
+    (
+        @nested [$($attrs:tt)*] [$($rel:tt)*] @[$($out:tt)*] ($($path:tt)*)
+        {{$($inner:tt)*} $($rest:tt)*}
+    ) => (
+        __prefix_items![
+            ($crate::__set_array) [@[$($out)*] ($($path)*)] []
+            ($($attrs)* $($rel)*) {$($inner)*} {$($rest)*}
+        ]
+    );
+
+    (
+        @nested [$($attrs:tt)*] [$($rel:tt)*] @[$($out:tt)*] ($($path:tt)*)
+        {$tail:ident}
+    ) => (
+        [$($out)* $($attrs)* $($path)*::$($rel)* $tail]
+    );
+
+    (
+        @nested [$($attrs:tt)*] [$($rel:tt)*] @[$($out:tt)*] ($($path:tt)*)
+        {$head:ident | $($rest:tt)*}
+    ) => (
+        __set_array![
+            @[$($out)* $($attrs)* $($path)*::$($rel)* $head,]
+            ($($path)*) {$($rest)*}
+        ]
+    );
+
+    (
+        @nested [$($attrs:tt)*] [$($rel:tt)*] @[$($out:tt)*] ($($path:tt)*)
+        {$head:ident , $($rest:tt)*}
+    ) => (
+        __set_array![
+            @[$($out)* $($attrs)* $($path)*::$($rel)* $head,]
+            ($($path)*) {$($rest)*}
+        ]
+    )
+}
+
+/// Emits an array expression of items with full paths (`{...}` of
+/// `{...} as ty`). The tokens of the current item are collected into
+/// `@item[...]`.
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __mixed_array {
+    ( @[$($out:tt)*] @item[$($item:tt)*] {} ) => (
+        [$($out)* $($item)*]
+    );
+
+    ( @[$($out:tt)*] @item[$($item:tt)*] {, $($rest:tt)*} ) => (
+        __mixed_array![@[$($out)* $($item)*,] @item[] {$($rest)*}]
+    );
+
+    ( @[$($out:tt)*] @item[$($item:tt)*] {| $($rest:tt)*} ) => (
+        __mixed_array![@[$($out)* $($item)*,] @item[] {$($rest)*}]
+    );
+
+    ( @[$($out:tt)*] @item[$($item:tt)*] {:: {$($inner:tt)*} $($rest:tt)*} ) => (
+        __prefix_items![
+            ($crate::__mixed_array) [@[$($out)*] @item[]] []
+            ($($item)* ::) {$($inner)*} {$($rest)*}
+        ]
+    );
+
+    ( @[$($out:tt)*] @item[$($item:tt)*] {$head:tt $($rest:tt)*} ) => (
+        __mixed_array![@[$($out)*] @item[$($item)* $head] {$($rest)*}]
+    )
+}
+
+/// Inserts `prefix` at the beginning of every item in `{items}` (after the
+/// item's own attributes) and passes `{items... rest...}` to the macro `$cb`,
+/// preceded by `$pre` and followed by `$post`. This flattens a group of a
+/// `use`-tree-like item list (`path::{A, B}` becomes `path::A, path::B`).
+///
+/// The state in the first `@(...)` is `start` at the beginning of an item,
+/// `sep` after a separator, and `mid` in the middle of an item. Separators
+/// are emitted only if another item follows.
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __prefix_items {
+    (
+        ($($cb:tt)*) [$($pre:tt)*] [$($post:tt)*] ($($prefix:tt)*)
+        {$($items:tt)*} {$($rest:tt)*}
+    ) => (
+        __prefix_items![
+            @(start) []
+            ($($cb)*) [$($pre)*] [$($post)*] ($($prefix)*) {$($items)*} {$($rest)*}
+        ]
+    );
+
+    // An empty group leaves no separator before `rest`
+    (
+        @($state:ident) []
+        ($($cb:tt)*) [$($pre:tt)*] [$($post:tt)*] ($($prefix:tt)*) {} {, $($rest:tt)*}
+    ) => (
+        $($cb)*![$($pre)* {$($rest)*} $($post)*]
+    );
+
+    (
+        @($state:ident) []
+        ($($cb:tt)*) [$($pre:tt)*] [$($post:tt)*] ($($prefix:tt)*) {} {| $($rest:tt)*}
+    ) => (
+        $($cb)*![$($pre)* {$($rest)*} $($post)*]
+    );
+
+    (
+        @($state:ident) [$($done:tt)*]
+        ($($cb:tt)*) [$($pre:tt)*] [$($post:tt)*] ($($prefix:tt)*) {} {$($rest:tt)*}
+    ) => (
+        $($cb)*![$($pre)* {$($done)* $($rest)*} $($post)*]
+    );
+
+    (
+        @(mid) [$($done:tt)*]
+        ($($cb:tt)*) [$($pre:tt)*] [$($post:tt)*] ($($prefix:tt)*)
+        {, $($items:tt)*} {$($rest:tt)*}
+    ) => (
+        __prefix_items![
+            @(sep) [$($done)*]
+            ($($cb)*) [$($pre)*] [$($post)*] ($($prefix)*) {$($items)*} {$($rest)*}
+        ]
+    );
+
+    (
+        @(mid) [$($done:tt)*]
+        ($($cb:tt)*) [$($pre:tt)*] [$($post:tt)*] ($($prefix:tt)*)
+        {| $($items:tt)*} {$($rest:tt)*}
+    ) => (
+        __prefix_items![
+            @(sep) [$($done)*]
+            ($($cb)*) [$($pre)*] [$($post)*] ($($prefix)*) {$($items)*} {$($rest)*}
+        ]
+    );
+
+    (
+        @(mid) [$($done:tt)*]
+        ($($cb:tt)*) [$($pre:tt)*] [$($post:tt)*] ($($prefix:tt)*)
+        {$head:tt $($items:tt)*} {$($rest:tt)*}
+    ) => (
+        __prefix_items![
+            @(mid) [$($done)* $head]
+            ($($cb)*) [$($pre)*] [$($post)*] ($($prefix)*) {$($items)*} {$($rest)*}
+        ]
+    );
+
+    (
+        @(start) [$($done:tt)*]
+        ($($cb:tt)*) [$($pre:tt)*] [$($post:tt)*] ($($prefix:tt)*)
+        {# $attr:tt $($items:tt)*} {$($rest:tt)*}
+    ) => (
+        __prefix_items![
+            @(start) [$($done)* # $attr]
+            ($($cb)*) [$($pre)*] [$($post)*] ($($prefix)*) {$($items)*} {$($rest)*}
+        ]
+    );
+
+    (
+        @(start) [$($done:tt)*]
+        ($($cb:tt)*) [$($pre:tt)*] [$($post:tt)*] ($($prefix:tt)*)
+        {$head:tt $($items:tt)*} {$($rest:tt)*}
+    ) => (
+        __prefix_items![
+            @(mid) [$($done)* $($prefix)* $head]
+            ($($cb)*) [$($pre)*] [$($post)*] ($($prefix)*) {$($items)*} {$($rest)*}
+        ]
+    );
+
+    (
+        @(sep) [$($done:tt)*]
+        ($($cb:tt)*) [$($pre:tt)*] [$($post:tt)*] ($($prefix:tt)*)
+        {$head:tt $($items:tt)*} {$($rest:tt)*}
+    ) => (
+        __prefix_items![
+            @(start) [$($done)* ,]
+            ($($cb)*) [$($pre)*] [$($post)*] ($($prefix)*) {$head $($items)*} {$($rest)*}
+        ]
+    )
+}
+
+/// Emits a constant expression of type `path::ty` given zero or more values
+/// of type `path::ty` defined as associated constants of `path::ty`.
+///
+/// Unlike [`flags`], the expansion only consists of `const fn` calls and
+/// thus can be used to initialize `const` and `static` items.
+///
+/// [`flags`]: macro.flags.html
+///
+/// # Syntax
+///
+/// ```text
+/// const_flags![path::ty::{Item1 | ... | ItemN}]
+/// const_flags![path::ty::{Item1, ..., ItemN}]
+/// const_flags![path::ty::{Item1 | ... | ItemN} retain]
+/// const_flags![path::ty::{Item1, ..., ItemN} retain]
+/// const_flags![path::{Item1 | ... | ItemN} as int_ty]
+/// const_flags![path::{Item1, ..., ItemN} as int_ty]
+/// ```
+///
+/// `Item1` ... `ItemN` are identifiers. The first two forms are for
+/// bitflags-like types providing `const fn bits` and
+/// `const fn from_bits_truncate` (such as the ones generated by [`bitflags`])
+/// and are expanded into:
+///
+/// ```text
+/// path::ty::from_bits_truncate(0 | path::ty::Item1.bits() | ... | path::ty::ItemN.bits())
+/// ```
+///
+/// The forms with `retain` call `const fn from_bits_retain` (provided by
+/// `bitflags` 2.x) instead of `from_bits_truncate`.
+///
+/// The last two forms are for integer constants of type `int_ty` and are
+/// expanded into:
+///
+/// ```text
+/// (0 as int_ty) | path::Item1 | ... | path::ItemN
+/// ```
+///
+/// [`bitflags`]: https://crates.io/crates/bitflags
+///
+/// # Examples
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     #[macro_use]
+///     extern crate bitflags;
+///
+///     bitflags! {
+///         struct Test: u32 {
+///             const A = 0b0001;
+///             const B = 0b0010;
+///         }
+///     }
+///
+///     mod values {
+///         pub const A: u32 = 0b0001;
+///         pub const B: u32 = 0b0010;
+///     }
+///
+///     const FLAGS0: Test = const_flags![Test::{}];
+///     const FLAGS2: Test = const_flags![Test::{A | B}];
+///     static MASK: u32 = const_flags![values::{A, B} as u32];
+///
+///     # fn main() {
+///     assert_eq!(FLAGS0, Test::empty());
+///     assert_eq!(FLAGS2, Test::A | Test::B);
+///     assert_eq!(MASK, 0b0011);
+///     # }
+///
+/// # Invalid usages
+///
+/// The path prefix (denoted as `path::ty::` in Section "Syntax") must not be
+/// empty.
+///
+/// Items can't have attributes because they are folded by `|`, which can't
+/// be conditionally compiled.
+///
+#[macro_export(local_inner_macros)]
+macro_rules! const_flags {
+    ( $($tt:tt)* ) => (
+        __frontend![const_flags ($crate::__const_flags) [@start] $($tt)*]
+    )
+}
+
+/// Folds items into a `|`-separated expression. `@bits(...)` is appended to
+/// every item.
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __const_flags {
+    ( @start @mixed $($rest:tt)* ) => (
+        __compile_error!("The path prefix (`A::` of `const_flags![A::{...}]`) must not be empty.")
+    );
+
+    ( @start ($($path:tt)*) {$($items:tt)*} as $int:ty ) => (
+        __const_flags![@[(0 as $int)] @bits() ($($path)*) {$($items)*}]
+    );
+
+    ( @start ($($path:tt)*) {$($items:tt)*} retain ) => (
+        <$($path)*>::from_bits_retain(
+            __const_flags![@[0] @bits(.bits()) ($($path)*) {$($items)*}]
+        )
+    );
+
+    ( @start ($($path:tt)*) {$($items:tt)*} ) => (
+        <$($path)*>::from_bits_truncate(
+            __const_flags![@[0] @bits(.bits()) ($($path)*) {$($items)*}]
+        )
+    );
+
+    ( @[$($out:tt)*] @bits($($bits:tt)*) ($($path:tt)*) {} ) => (
+        $($out)*
+    );
+
+    ( @[$($out:tt)*] @bits($($bits:tt)*) ($($path:tt)*) {.. $($rest:tt)*} ) => (
+        __compile_error!("`..` is only supported by `flags!`.")
+    );
+
+    ( @[$($out:tt)*] @bits($($bits:tt)*) ($($path:tt)*) {# $($rest:tt)*} ) => (
+        __compile_error!(
+            "Attributes on items are not supported by `const_flags!` because its items are \
+             folded by `|`."
+        )
+    );
+
+    ( @[$($out:tt)*] @bits($($bits:tt)*) ($($path:tt)*) {$tail:ident} ) => (
+        $($out)* | $($path)*::$tail $($bits)*
+    );
+
+    ( @[$($out:tt)*] @bits($($bits:tt)*) ($($path:tt)*) {$head:ident | $($rest:tt)*} ) => (
+        __const_flags![
+            @[$($out)* | $($path)*::$head $($bits)*]
+            @bits($($bits)*)
+            ($($path)*) {$($rest)*}
+        ]
+    );
+
+    ( @[$($out:tt)*] @bits($($bits:tt)*) ($($path:tt)*) {$head:ident , $($rest:tt)*} ) => (
+        __const_flags![
+            @[$($out)* | $($path)*::$head $($bits)*]
+            @bits($($bits)*)
+            ($($path)*) {$($rest)*}
+        ]
+    )
+}
+
+/// Emits a constant expression folding zero or more integer constants by `|`.
+///
+/// # Syntax
+///
+/// ```text
+/// bits![path::{Item1 | ... | ItemN}]
+/// bits![path::{Item1, ..., ItemN}]
+/// bits![path::{Item1 | ... | ItemN} as int_ty]
+/// bits![path::{Item1, ..., ItemN} as int_ty]
+/// ```
+///
+/// The input is in the syntax of [`set_array`], so items may have attributes
+/// and nested paths, and `bits![{path1::Item1, ...} as int_ty]` accepts items
+/// without a common prefix. The items are folded by `|` in a loop, which
+/// works for all primitive integer types and in a constant context:
+///
+/// ```text
+/// {
+///     let items = set_array![path::{Item1, ..., ItemN}];
+///     let mut bits = 0;
+///     let mut i = 0;
+///     while i < items.len() {
+///         bits |= items[i];
+///         i += 1;
+///     }
+///     bits
+/// }
+/// ```
+///
+/// The result type is inferred from the items. `as int_ty` specifies it
+/// explicitly and is required if the item list is empty.
+///
+/// Unlike [`const_flags`], the items can't be bitflags-like types, which
+/// don't support `|` in a constant context.
+///
+/// [`set_array`]: macro.set_array.html
+/// [`const_flags`]: macro.const_flags.html
+///
+/// # Examples
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     mod libc {
+///         pub const O_RDONLY: i32 = 0;
+///         pub const O_CREAT: i32 = 0o100;
+///         pub const O_TRUNC: i32 = 0o1000;
+///     }
+///     mod regs {
+///         pub const EN: u8 = 0x01;
+///         pub const IRQ: u8 = 0x80;
+///     }
+///
+///     const OFLAGS: i32 = bits![libc::{O_RDONLY | O_CREAT | O_TRUNC}];
+///     static CTRL: u8 = bits![regs::{EN, #[cfg(any())] IRQ}];
+///
+///     # fn main() {
+///     assert_eq!(OFLAGS, 0o1100);
+///     assert_eq!(CTRL, 0x01);
+///     assert_eq!(bits![regs::{} as u8], 0);
+///     # }
+#[macro_export(local_inner_macros)]
+macro_rules! bits {
+    ( $($tt:tt)* ) => ({
+        let items = set_array![$($tt)*];
+        let mut bits = 0;
+        let mut i = 0;
+        while i < items.len() {
+            bits |= items[i];
+            i += 1;
+        }
+        bits
+    })
+}
+
+/// Compares a set of flags against patterns written in the syntax of
+/// [`flags`] and evaluates the body of the first matching arm.
+///
+/// [`flags`]: macro.flags.html
+///
+/// # Syntax
+///
+/// ```text
+/// flags_match!(value, {
+///     all path::ty::{...} => expr1,
+///     any path::ty::{...} => expr2,
+///     exactly path::ty::{...} => expr3,
+///     none path::ty::{...} => expr4,
+///     _ => expr5,
+/// })
+/// ```
+///
+/// The arms are tested in order. Each arm matches if `value` (evaluated only
+/// once) contains all of the given flags (`all`), at least one of them
+/// (`any`), exactly them (`exactly`), or none of them (`none`). `_` matches
+/// any value and must be the last arm if present. If no arm matches and `_`
+/// is absent, the result is `()`.
+///
+/// The comparisons are done by bitwise operations and `==`, so the set type
+/// must implement `Copy`, `BitAnd`, and `PartialEq` (as the ones of
+/// [`bitflags`] and [`enumflags`] do).
+///
+/// [`bitflags`]: https://crates.io/crates/bitflags
+/// [`enumflags`]: https://crates.io/crates/enumflags
+///
+/// # Examples
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     #[macro_use]
+///     extern crate bitflags;
+///     # fn main() {
+///     bitflags! {
+///         struct Test: u32 {
+///             const A = 0b0001;
+///             const B = 0b0010;
+///             const C = 0b0100;
+///         }
+///     }
+///
+///     let describe = |value: Test| flags_match!(value, {
+///         exactly Test::{} => "empty",
+///         all Test::{A | B} => "A and B",
+///         any Test::{A | B} => "A or B",
+///         _ => "C only",
+///     });
+///
+///     assert_eq!(describe(Test::empty()), "empty");
+///     assert_eq!(describe(Test::all()), "A and B");
+///     assert_eq!(describe(Test::B | Test::C), "A or B");
+///     assert_eq!(describe(Test::C), "C only");
+///     # }
+#[macro_export(local_inner_macros)]
+macro_rules! flags_match {
+    ( $value:expr, { $($arms:tt)* } ) => ({
+        let value = $value;
+        __flags_match![@value(value) @[] $($arms)*]
+    })
+}
+
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __flags_match {
+    // The end of the arms
+    ( @value($value:ident) @[$($out:tt)*] ) => (
+        $($out)* {}
+    );
+
+    ( @value($value:ident) @[$($out:tt)*] _ => $body:expr $(,)* ) => (
+        $($out)* { $body }
+    );
+
+    ( @value($value:ident) @[$($out:tt)*] $kind:ident $($rest:tt)* ) => (
+        __flags_match![@value($value) @[$($out)*] @kind($kind) @path() $($rest)*]
+    );
+
+    // Find the end of `path::ty::{...}`
+    (
+        @value($value:ident) @[$($out:tt)*] @kind($kind:ident) @path($($path:tt)*)
+        {$($items:tt)*} => $body:expr, $($rest:tt)*
+    ) => (
+        __flags_match![
+            @value($value)
+            @[$($out)* if __flags_match![@cond $kind $value ($($path)*) {$($items)*}] {
+                $body
+            } else]
+            $($rest)*
+        ]
+    );
+
+    (
+        @value($value:ident) @[$($out:tt)*] @kind($kind:ident) @path($($path:tt)*)
+        {$($items:tt)*} => $body:expr
+    ) => (
+        __flags_match![@value($value) @[$($out)*] @kind($kind) @path($($path)*)
+            {$($items)*} => $body,]
+    );
+
+    (
+        @value($value:ident) @[$($out:tt)*] @kind($kind:ident) @path($($path:tt)*)
+        {$($items:tt)*} => $body:block $($rest:tt)*
+    ) => (
+        __flags_match![@value($value) @[$($out)*] @kind($kind) @path($($path)*)
+            {$($items)*} => $body, $($rest)*]
+    );
+
+    (
+        @value($value:ident) @[$($out:tt)*] @kind($kind:ident) @path($($path:tt)*)
+        $head:tt $($rest:tt)*
+    ) => (
+        __flags_match![@value($value) @[$($out)*] @kind($kind) @path($($path)* $head) $($rest)*]
+    );
+
+    // The conditions
+    ( @cond all $value:ident ($($path:tt)*) {$($items:tt)*} ) => ({
+        let mask = flags![$($path)* {$($items)*}];
+        ($value & mask) == mask
+    });
+
+    ( @cond any $value:ident ($($path:tt)*) {$($items:tt)*} ) => (
+        ($value & flags![$($path)* {$($items)*}]) != flags![$($path)* {}]
+    );
+
+    ( @cond exactly $value:ident ($($path:tt)*) {$($items:tt)*} ) => (
+        $value == flags![$($path)* {$($items)*}]
+    );
+
+    ( @cond none $value:ident ($($path:tt)*) {$($items:tt)*} ) => (
+        ($value & flags![$($path)* {$($items)*}]) == flags![$($path)* {}]
+    );
+
+    ( @cond $kind:ident $($rest:tt)* ) => (
+        __compile_error!(
+            "Expected `all`, `any`, `exactly`, `none`, or `_` at the beginning of an arm of `flags_match!`."
+        )
+    );
+}
+
+/// Checks if a set of flags contains all of the given flags.
+///
+/// The flags are specified in the syntax of [`set_array`], and the check is
+/// done by [`SetOps::contains_all`]. For set types of
+/// [`BitOrExtendDefaultSet`] (e.g., `flagset`), it's done by the bitwise
+/// operators of the set type instead. [`has_any`] and [`has_none`] are the
+/// counterparts checking if the set contains at least one or none of the
+/// flags, respectively.
+///
+/// [`set_array`]: macro.set_array.html
+/// [`SetOps::contains_all`]: trait.SetOps.html#tymethod.contains_all
+/// [`BitOrExtendDefaultSet`]: trait.BitOrExtendDefaultSet.html
+/// [`has_any`]: macro.has_any.html
+/// [`has_none`]: macro.has_none.html
+///
+/// # Syntax
+///
+/// ```text
+/// has_all!(value, path::ty::{Item1 | ... | ItemN})
+/// has_all!(value, path::ty::{Item1, ..., ItemN})
+/// ```
+///
+/// # Examples
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     #[macro_use]
+///     extern crate bitflags;
+///     # fn main() {
+///     bitflags! {
+///         struct Test: u32 {
+///             const A = 0b0001;
+///             const B = 0b0010;
+///             const C = 0b0100;
+///         }
+///     }
+///
+///     let value = flags![Test::{A | B}];
+///
+///     assert!(has_all!(value, Test::{A | B}));
+///     assert!(!has_all!(value, Test::{A | C}));
+///     assert!(has_any!(value, Test::{A, C}));
+///     assert!(has_none!(value, Test::{C}));
+///     assert!(has_all!(value, Test::{}));
+///     assert!(!has_any!(value, Test::{}));
+///     # }
+#[macro_export(local_inner_macros)]
+macro_rules! has_all {
+    ( $value:expr, $($tt:tt)* ) => (
+        __set_kit!(__containing_type![$($tt)*]).set_contains_all(
+            &$value,
+            &set_array![$($tt)*],
+        )
+    )
+}
+
+/// Checks if a set of flags contains at least one of the given flags.
+///
+/// See [`has_all`] for the syntax.
+///
+/// [`has_all`]: macro.has_all.html
+#[macro_export(local_inner_macros)]
+macro_rules! has_any {
+    ( $value:expr, $($tt:tt)* ) => (
+        __set_kit!(__containing_type![$($tt)*]).set_intersects(
+            &$value,
+            &set_array![$($tt)*],
+        )
+    )
+}
+
+/// Checks if a set of flags contains none of the given flags.
+///
+/// See [`has_all`] for the syntax.
+///
+/// [`has_all`]: macro.has_all.html
+#[macro_export(local_inner_macros)]
+macro_rules! has_none {
+    ( $value:expr, $($tt:tt)* ) => (
+        !__set_kit!(__containing_type![$($tt)*]).set_intersects(
+            &$value,
+            &set_array![$($tt)*],
+        )
+    )
+}
+
+/// Inserts, removes, and toggles flags of a set in place.
+///
+/// Each item is preceded by `+` (insert), `-` (remove), or `^` (toggle). The
+/// items are collected into three arrays, which are applied by
+/// [`UpdateSet::update`] in this order. For bitflags-like types, this amounts
+/// to three mask operations (`|`, `& !`, and `^`) however many items there
+/// are. Set types of [`BitOrExtendDefaultSet`] (e.g., `flagset`) are updated
+/// by the same mask operations without `UpdateSet`.
+///
+/// [`UpdateSet::update`]: trait.UpdateSet.html#tymethod.update
+/// [`BitOrExtendDefaultSet`]: trait.BitOrExtendDefaultSet.html
+///
+/// # Syntax
+///
+/// ```text
+/// update_flags!(value, path::ty::{+Item1, -Item2, ..., ^ItemN})
+/// update_flags!(value, path::ty::{+Item1 | -Item2 | ... | ^ItemN})
+/// ```
+///
+/// `value` is a place expression of the set type. Like the ones of [`flags`],
+/// items may be preceded by attributes such as `#[cfg]`.
+///
+/// [`flags`]: macro.flags.html
+///
+/// # Examples
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     #[macro_use]
+///     extern crate bitflags;
+///     # fn main() {
+///     bitflags! {
+///         struct Test: u32 {
+///             const A = 0b0001;
+///             const B = 0b0010;
+///             const C = 0b0100;
+///         }
+///     }
+///
+///     let mut value = flags![Test::{B, C}];
+///     update_flags!(value, Test::{+A, -B});
+///     assert_eq!(value, Test::A | Test::C);
+///
+///     update_flags!(value, Test::{^A | ^B});
+///     assert_eq!(value, Test::B | Test::C);
+///     # }
+#[macro_export(local_inner_macros)]
+macro_rules! update_flags {
+    ( $value:expr, $($tt:tt)* ) => (
+        __frontend![update_flags ($crate::__update_flags) [($value)] $($tt)*]
+    )
+}
+
+/// Implements `update_flags!`. The inserted, removed, and toggled items are
+/// collected into the first, second, and third `@[...]`, respectively.
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __update_flags {
+    ( ($value:expr) @mixed $($rest:tt)* ) => (
+        __compile_error!("`update_flags!` requires a common path prefix (`A::{...}`).")
+    );
+
+    ( ($value:expr) ($($path:tt)*) {$($items:tt)*} ) => (
+        __update_flags![@[] @[] @[] ($value) ($($path)*) {$($items)*}]
+    );
+
+    ( @[$($ins:tt)*] @[$($rem:tt)*] @[$($tog:tt)*] ($value:expr) ($($path:tt)*) {} ) => (
+        __set_kit!($($path)*).set_update(
+            &mut $value,
+            &[$($ins)*],
+            &[$($rem)*],
+            &[$($tog)*],
+        )
+    );
+
+    (
+        @[$($ins:tt)*] @[$($rem:tt)*] @[$($tog:tt)*] ($value:expr) ($($path:tt)*)
+        {$(#[$attr:meta])* + $item:ident $($rest:tt)*}
+    ) => (
+        __update_flags![
+            @sep @[$($ins)* $(#[$attr])* $($path)*::$item,] @[$($rem)*] @[$($tog)*]
+            ($value) ($($path)*) {$($rest)*}
+        ]
+    );
+
+    (
+        @[$($ins:tt)*] @[$($rem:tt)*] @[$($tog:tt)*] ($value:expr) ($($path:tt)*)
+        {$(#[$attr:meta])* - $item:ident $($rest:tt)*}
+    ) => (
+        __update_flags![
+            @sep @[$($ins)*] @[$($rem)* $(#[$attr])* $($path)*::$item,] @[$($tog)*]
+            ($value) ($($path)*) {$($rest)*}
+        ]
+    );
+
+    (
+        @[$($ins:tt)*] @[$($rem:tt)*] @[$($tog:tt)*] ($value:expr) ($($path:tt)*)
+        {$(#[$attr:meta])* ^ $item:ident $($rest:tt)*}
+    ) => (
+        __update_flags![
+            @sep @[$($ins)*] @[$($rem)*] @[$($tog)* $(#[$attr])* $($path)*::$item,]
+            ($value) ($($path)*) {$($rest)*}
+        ]
+    );
+
+    ( @[$($ins:tt)*] @[$($rem:tt)*] @[$($tog:tt)*] $($rest:tt)* ) => (
+        __compile_error!(
+            "Every item of `update_flags!` must be preceded by `+` (insert), `-` (remove), or `^` (toggle)."
+        )
+    );
+
+    // Separators
+    ( @sep @[$($ins:tt)*] @[$($rem:tt)*] @[$($tog:tt)*] $value:tt $path:tt {} ) => (
+        __update_flags![@[$($ins)*] @[$($rem)*] @[$($tog)*] $value $path {}]
+    );
+
+    ( @sep @[$($ins:tt)*] @[$($rem:tt)*] @[$($tog:tt)*] $value:tt $path:tt {, $($rest:tt)*} ) => (
+        __update_flags![@[$($ins)*] @[$($rem)*] @[$($tog)*] $value $path {$($rest)*}]
+    );
+
+    ( @sep @[$($ins:tt)*] @[$($rem:tt)*] @[$($tog:tt)*] $value:tt $path:tt {| $($rest:tt)*} ) => (
+        __update_flags![@[$($ins)*] @[$($rem)*] @[$($tog)*] $value $path {$($rest)*}]
+    );
+
+    ( @sep $($rest:tt)* ) => (
+        __compile_error!("Items of `update_flags!` must be separated by `|` or `,`.")
+    )
+}
+
+/// Implements [`DefaultSet`] for types without bit values (such as plain
+/// enums) by choosing a collection type as their set type.
+///
+/// [`DefaultSet`]: trait.DefaultSet.html
+///
+/// # Syntax
+///
+/// ```text
+/// impl_default_set!(ty1 => Set1, ..., tyN => SetN);
+/// ```
+///
+/// `Set1` ... `SetN` are one of the following:
+///
+///  - `BTreeSet` (requires the `alloc` feature)
+///  - `Vec` (requires the `alloc` feature)
+///  - `HashSet` (requires the `std` feature)
+///
+/// This macro is equivalent to manually implementing `DefaultSet`,
+/// [`SetOps`], [`UpdateSet`], and [`DiffSet`], which is also possible for
+/// other set types.
+///
+/// [`SetOps`]: trait.SetOps.html
+/// [`UpdateSet`]: trait.UpdateSet.html
+/// [`DiffSet`]: trait.DiffSet.html
+///
+/// # Examples
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     # #[cfg(feature = "alloc")]
+///     # fn main() {
+///     use std::collections::BTreeSet;
+///
+///     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+///     enum Color { Red, Green, Blue }
+///
+///     impl_default_set!(Color => BTreeSet);
+///
+///     let colors = flags![Color::{Red | Blue}];
+///
+///     let expected: BTreeSet<_> = [Color::Red, Color::Blue].iter().cloned().collect();
+///     assert_eq!(colors, expected);
+///     # }
+///     # #[cfg(not(feature = "alloc"))]
+///     # fn main() {}
+#[macro_export(local_inner_macros)]
+macro_rules! impl_default_set {
+    ( $($ty:ty => $set:ident),* $(,)* ) => (
+        $( __impl_default_set!($set $ty); )*
+    )
+}
+
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __impl_default_set {
+    ( BTreeSet $ty:ty ) => ( __impl_default_set_alloc!(BTreeSet $ty); );
+    ( Vec $ty:ty ) => ( __impl_default_set_alloc!(Vec $ty); );
+    ( HashSet $ty:ty ) => ( __impl_default_set_std!(HashSet $ty); );
+    ( $set:ident $ty:ty ) => (
+        __compile_error!(
+            "Unsupported set type. Only `BTreeSet`, `Vec`, and `HashSet` are supported."
+        );
+    )
+}
+
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_default_set_alloc {
+    ( BTreeSet $ty:ty ) => {
+        impl $crate::DefaultSet for $ty {
+            type Set = $crate::__alloc::collections::BTreeSet<$ty>;
+        }
+        $crate::__impl_set_ops_by_contains!($crate::__alloc::collections::BTreeSet<$ty>, $ty);
+        $crate::__impl_diff_set_by_contains!($crate::__alloc::collections::BTreeSet<$ty>, $ty);
+        $crate::__impl_update_set_by_insert!($crate::__alloc::collections::BTreeSet<$ty>, $ty);
+    };
+    ( Vec $ty:ty ) => {
+        impl $crate::DefaultSet for $ty {
+            type Set = $crate::__alloc::vec::Vec<$ty>;
+        }
+        $crate::__impl_set_ops_by_contains!($crate::__alloc::vec::Vec<$ty>, $ty);
+        $crate::__impl_diff_set_by_contains!($crate::__alloc::vec::Vec<$ty>, $ty);
+
+        impl $crate::UpdateSet<$ty> for $crate::__alloc::vec::Vec<$ty> {
+            fn update(&mut self, insert: &[$ty], remove: &[$ty], toggle: &[$ty]) {
+                for item in insert {
+                    if !self.contains(item) {
+                        self.push(item.clone());
+                    }
+                }
+                self.retain(|item| !remove.contains(item));
+                for item in toggle {
+                    match self.iter().position(|x| x == item) {
+                        Some(i) => {
+                            self.remove(i);
+                        }
+                        None => self.push(item.clone()),
+                    }
+                }
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "alloc"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_default_set_alloc {
+    ( $set:ident $ty:ty ) => {
+        compile_error!("This set type requires the `alloc` feature of `flags-macro`.");
+    };
+}
+
+#[cfg(feature = "std")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_default_set_std {
+    ( HashSet $ty:ty ) => {
+        impl $crate::DefaultSet for $ty {
+            type Set = $crate::__std::collections::HashSet<$ty>;
+        }
+        $crate::__impl_set_ops_by_contains!($crate::__std::collections::HashSet<$ty>, $ty);
+        $crate::__impl_diff_set_by_contains!($crate::__std::collections::HashSet<$ty>, $ty);
+        $crate::__impl_update_set_by_insert!($crate::__std::collections::HashSet<$ty>, $ty);
+    };
+}
+
+#[cfg(not(feature = "std"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_default_set_std {
+    ( $set:ident $ty:ty ) => {
+        compile_error!("This set type requires the `std` feature of `flags-macro`.");
+    };
+}
+
+/// Implements `SetOps<$ty>` for a collection type `$set` having a method
+/// `contains(&$ty) -> bool`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_set_ops_by_contains {
+    ( $set:ty, $ty:ty ) => {
+        impl $crate::SetOps<$ty> for $set {
+            fn contains_all(&self, items: &[$ty]) -> bool {
+                items.iter().all(|item| self.contains(item))
+            }
+
+            fn intersects(&self, items: &[$ty]) -> bool {
+                items.iter().any(|item| self.contains(item))
+            }
+        }
+    };
+}
+
+/// Implements `DiffSet<$ty>` for a collection type `$set` having methods
+/// `iter()` and `contains(&$ty) -> bool`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_diff_set_by_contains {
+    ( $set:ty, $ty:ty ) => {
+        impl $crate::DiffSet<$ty> for $set {
+            fn without(&self, other: &Self) -> Self {
+                self.iter()
+                    .filter(|item| !other.contains(item))
+                    .cloned()
+                    .collect()
+            }
+
+            fn common(&self, other: &Self) -> Self {
+                self.iter()
+                    .filter(|item| other.contains(item))
+                    .cloned()
+                    .collect()
+            }
+
+            fn cover_flag(&self, flag: $ty, covered: &mut Self) -> bool {
+                if self.contains(&flag) && !covered.contains(&flag) {
+                    covered.extend(Some(flag));
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    };
+}
+
+/// Implements `UpdateSet<$ty>` for a collection type `$set` having methods
+/// `insert($ty) -> bool` and `remove(&$ty) -> bool`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_update_set_by_insert {
+    ( $set:ty, $ty:ty ) => {
+        impl $crate::UpdateSet<$ty> for $set {
+            fn update(&mut self, insert: &[$ty], remove: &[$ty], toggle: &[$ty]) {
+                self.extend(insert.iter().cloned());
+                for item in remove {
+                    self.remove(item);
+                }
+                for item in toggle {
+                    if !self.remove(item) {
+                        self.insert(item.clone());
+                    }
+                }
+            }
+        }
+    };
+}
+
+/// Implements [`FlagNames`] for bitflags-like types by listing the names of
+/// their flags, which are associated constants or enumerate items.
+///
+/// [`FlagNames`]: trait.FlagNames.html
+///
+/// # Syntax
+///
+/// ```text
+/// impl_flag_names!(ty1 => {Item1, ..., ItemN}, ..., tyN => {...});
+/// impl_flag_names!(ty1 => {Item1, ..., ItemN} with bits_fn, ...);
+/// impl_flag_names!(ty1 as bitflags2, ..., tyN as bitflags2);
+/// ```
+///
+/// `bits_fn` is an expression of type `fn(&ty) -> int` (such as
+/// `|flag| flag.bits()`) used to implement `FlagNames::flag_bits`.
+///
+/// `ty as bitflags2` takes the names and the bits from `bitflags::Flags`
+/// (`bitflags` 2.x) instead of listing them, and requires the `bitflags2`
+/// feature.
+///
+/// # Examples
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     #[macro_use]
+///     extern crate bitflags;
+///
+///     bitflags! {
+///         struct Test: u32 {
+///             const A = 0b0001;
+///             const B = 0b0010;
+///         }
+///     }
+///
+///     impl_flag_names!(Test => {A, B});
+///
+///     # fn main() {
+///     assert_eq!(flags_macro::parse("A | B"), Ok(Test::A | Test::B));
+///     # }
+#[macro_export(local_inner_macros)]
+macro_rules! impl_flag_names {
+    ( $($ty:ty as bitflags2),+ $(,)* ) => (
+        $(__impl_flag_names_bitflags2!($ty);)+
+    );
+
+    ( $($ty:ty => {$($items:ident),* $(,)*} $(with $bits:expr)?),* $(,)* ) => (
+        $(
+            impl $crate::FlagNames for $ty {
+                fn from_flag_name(name: &str) -> Option<Self> {
+                    $(
+                        if name == __stringify!($items) {
+                            return Some(<$ty>::$items);
+                        }
+                    )*
+                    None
+                }
+
+                fn nth_flag_name(index: usize) -> Option<&'static str> {
+                    const NAMES: &[&str] = &[$(__stringify!($items)),*];
+                    NAMES.get(index).cloned()
+                }
+
+                $(
+                    fn flag_bits(&self) -> Option<u64> {
+                        let bits: fn(&$ty) -> _ = $bits;
+                        Some(bits(self) as u64)
+                    }
+                )?
+            }
+        )*
+    )
+}
+
+#[cfg(feature = "bitflags2")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_flag_names_bitflags2 {
+    ( $ty:ty ) => {
+        impl $crate::FlagNames for $ty {
+            fn from_flag_name(name: &str) -> Option<Self> {
+                $crate::__private::flags_from_name(name)
+            }
+
+            fn nth_flag_name(index: usize) -> Option<&'static str> {
+                $crate::__private::flags_nth_name::<$ty>(index)
+            }
+
+            fn flag_bits(&self) -> Option<u64> {
+                $crate::__private::flags_bits(self)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "bitflags2"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_flag_names_bitflags2 {
+    ( $ty:ty ) => {
+        compile_error!(
+            "`impl_flag_names!(T as bitflags2)` requires the `bitflags2` feature of `flags-macro`."
+        );
+    };
+}
+
+/// `stringify!` callable from `local_inner_macros` macros.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __stringify {
+    ( $($tt:tt)* ) => (stringify!($($tt)*))
+}
+
+/// `concat!` callable from `local_inner_macros` macros.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __concat {
+    ( $($tt:tt)* ) => (concat!($($tt)*))
+}
+
+/// A trait for getting the default "set" type from an "element" type.
+///
+/// Bitflags-like types don't have to implement this trait because they are
+/// covered by [`BitOrDefaultSet`]. Implementing this trait for such a type
+/// overrides the set type chosen by `BitOrDefaultSet`.
+///
+/// This trait can be implemented manually, by using [`impl_default_set`], or
+/// by `#[derive(DefaultSet)]` (requires the `derive` feature):
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     # #[cfg(feature = "derive")]
+///     # fn main() {
+///     # use std::iter::FromIterator;
+///     use flags_macro::DefaultSet;
+///
+///     #[derive(Debug, PartialEq)]
+///     struct ColorSet(Vec<Color>);
+///
+///     impl FromIterator<Color> for ColorSet {
+///         // ...
+///     #   fn from_iter<I: IntoIterator<Item = Color>>(iter: I) -> Self {
+///     #       ColorSet(iter.into_iter().collect())
+///     #   }
+///     }
+///
+///     #[derive(Debug, Clone, Copy, PartialEq, DefaultSet)]
+///     #[default_set(ColorSet)]
+///     enum Color { Red, Green, Blue }
+///
+///     assert_eq!(flags![Color::{Red, Blue}], ColorSet(vec![Color::Red, Color::Blue]));
+///     # }
+///     # #[cfg(not(feature = "derive"))]
+///     # fn main() {}
+///
+/// [`BitOrDefaultSet`]: trait.BitOrDefaultSet.html
+/// [`impl_default_set`]: macro.impl_default_set.html
+pub trait DefaultSet: Sized {
+    type Set: FromIterator<Self>;
+
+    /// Construct a `Set` using `Set::from_iter`.
+    fn set_from_iter(iter: impl IntoIterator<Item = Self>) -> Self::Set {
+        Self::Set::from_iter(iter)
+    }
+
+    /// Construct an empty `Set`.
+    fn set_empty() -> Self::Set {
+        Self::set_from_iter(empty())
+    }
+
+    /// Construct a `Set` containing every value.
+    fn set_all() -> Self::Set
+    where
+        Self::Set: Not<Output = Self::Set>,
+    {
+        Self::set_complement_from_iter(empty())
+    }
+
+    /// Construct a `Set` containing every value except the ones from `iter`.
+    fn set_complement_from_iter(iter: impl IntoIterator<Item = Self>) -> Self::Set
+    where
+        Self::Set: Not<Output = Self::Set>,
+    {
+        !Self::set_from_iter(iter)
+    }
+
+    /// Construct a `Set` containing the values from both of `set` and `other`.
+    fn set_union(mut set: Self::Set, other: Self::Set) -> Self::Set
+    where
+        Self::Set: Extend<Self> + IntoIterator<Item = Self>,
+    {
+        set.extend(other);
+        set
+    }
+
+    /// Construct a `Set` containing the values in both `set` and `other`.
+    fn set_intersection(set: Self::Set, other: Self::Set) -> Self::Set
+    where
+        for<'a> &'a Self::Set: BitAnd<&'a Self::Set, Output = Self::Set>,
+    {
+        &set & &other
+    }
+
+    /// Construct a `Set` containing the values in `set` but not in `other`.
+    fn set_difference(set: Self::Set, other: Self::Set) -> Self::Set
+    where
+        for<'a> &'a Self::Set: Sub<&'a Self::Set, Output = Self::Set>,
+    {
+        &set - &other
+    }
+
+    /// Construct a `Set` containing the values in exactly one of `set` and
+    /// `other`.
+    fn set_symmetric_difference(set: Self::Set, other: Self::Set) -> Self::Set
+    where
+        for<'a> &'a Self::Set: BitXor<&'a Self::Set, Output = Self::Set>,
+    {
+        &set ^ &other
+    }
+
+    /// Construct a `Set` containing every value not in `set`.
+    fn set_complement(set: Self::Set) -> Self::Set
+    where
+        Self::Set: Not<Output = Self::Set>,
+    {
+        !set
+    }
+}
+
+/// Provides the default "set" type of bitflags-like types, which is used by
+/// [`flags`] unless the type implements [`DefaultSet`].
+///
+/// This trait has a blanket implementation for any `T` such that
+/// `<T as BitOr>::Output` implements `FromIterator<T>`.
+///
+/// [`flags`]: macro.flags.html
+/// [`DefaultSet`]: trait.DefaultSet.html
+pub trait BitOrDefaultSet: Sized {
+    type Set: FromIterator<Self>;
+}
+
+impl<T> BitOrDefaultSet for T
+where
+    T: BitOr,
+    <T as BitOr>::Output: FromIterator<Self>,
+{
+    type Set = <T as BitOr>::Output;
+}
+
+/// Provides the default "set" type of bitflags-like types whose set type
+/// implements `Default` and `Extend` instead of `FromIterator` (e.g.,
+/// [`flagset`]). This is used by [`flags`] unless the type implements
+/// [`DefaultSet`] or [`BitOrDefaultSet`].
+///
+/// This trait has a blanket implementation for any `T` such that
+/// `<T as BitOr>::Output` implements `Default` and `Extend<T>`. Exclusion
+/// (`{*}`, `{* - A}`, `{!A}`) additionally requires the set type to implement
+/// `Not` and `IntoIterator<Item = T>`.
+///
+/// [`flagset`]: https://crates.io/crates/flagset
+/// [`flags`]: macro.flags.html
+/// [`DefaultSet`]: trait.DefaultSet.html
+/// [`BitOrDefaultSet`]: trait.BitOrDefaultSet.html
+pub trait BitOrExtendDefaultSet: Sized {
+    type Set: Default + Extend<Self>;
+}
+
+impl<T> BitOrExtendDefaultSet for T
+where
+    T: BitOr,
+    <T as BitOr>::Output: Default + Extend<Self>,
+{
+    type Set = <T as BitOr>::Output;
+}
+
+/// Provides containment checks of a set against flags of type `T`. This is
+/// used by [`has_all`], [`has_any`], and [`has_none`].
+///
+/// This trait has a blanket implementation for bitflags-like types, i.e.,
+/// any `S` such that `T: BitOr<Output = S>` and `S` implements
+/// `FromIterator<T>` and `BitAnd<Output = S>`. [`impl_default_set`]
+/// implements it for the chosen collection type. Set types collected by
+/// `Extend` instead of `FromIterator` (e.g., `flagset`) don't implement it;
+/// the macros check them by the bitwise operators directly.
+///
+/// [`has_all`]: macro.has_all.html
+/// [`has_any`]: macro.has_any.html
+/// [`has_none`]: macro.has_none.html
+/// [`impl_default_set`]: macro.impl_default_set.html
+pub trait SetOps<T> {
+    /// Checks if `self` contains all of `items`. Returns `true` if `items` is
+    /// empty.
+    fn contains_all(&self, items: &[T]) -> bool;
+
+    /// Checks if `self` contains at least one of `items`. Returns `false` if
+    /// `items` is empty.
+    fn intersects(&self, items: &[T]) -> bool;
+}
+
+impl<T, S> SetOps<T> for S
+where
+    T: BitOr<Output = S> + Copy,
+    S: FromIterator<T> + BitAnd<Output = S> + Copy + PartialEq,
+{
+    fn contains_all(&self, items: &[T]) -> bool {
+        let mask: S = items.iter().cloned().collect();
+        (*self & mask) == mask
+    }
+
+    fn intersects(&self, items: &[T]) -> bool {
+        let mask: S = items.iter().cloned().collect();
+        (*self & mask) != S::from_iter(empty())
+    }
+}
+
+/// Inserts, removes, and toggles flags of type `T` in a set in place. This is
+/// used by [`update_flags`].
+///
+/// This trait has a blanket implementation for bitflags-like types, i.e.,
+/// any `S` such that `T: BitOr<Output = S>` and `S` implements
+/// `FromIterator<T>`, `BitOr`, `BitAnd`, `BitXor`, and `Not` (all with
+/// `Output = S`). [`impl_default_set`] implements it for the chosen
+/// collection type. Set types collected by `Extend` instead of
+/// `FromIterator` (e.g., `flagset`) don't implement it; [`update_flags`]
+/// updates them by the bitwise operators directly.
+///
+/// [`update_flags`]: macro.update_flags.html
+/// [`impl_default_set`]: macro.impl_default_set.html
+pub trait UpdateSet<T> {
+    /// Inserts `insert`, removes `remove`, and then toggles `toggle`. Thus an
+    /// item appearing in both `insert` and `remove` is removed.
+    fn update(&mut self, insert: &[T], remove: &[T], toggle: &[T]);
+}
+
+impl<T, S> UpdateSet<T> for S
+where
+    T: BitOr<Output = S> + Copy,
+    S: FromIterator<T>
+        + BitOr<Output = S>
+        + BitAnd<Output = S>
+        + BitXor<Output = S>
+        + Not<Output = S>
+        + Copy,
+{
+    fn update(&mut self, insert: &[T], remove: &[T], toggle: &[T]) {
+        let mask = |items: &[T]| -> S { items.iter().cloned().collect() };
+        *self = ((*self | mask(insert)) & !mask(remove)) ^ mask(toggle);
+    }
+}
+
+/// A bitflags-like set of flags of type `T`, which can be collected from the
+/// flags and combined by `&`, `|`, and `^`. This is a shorthand for the
+/// bounds of [`display`] and [`DiffSet`], and is implemented for all types
+/// satisfying them.
+///
+/// [`display`]: fn.display.html
+/// [`DiffSet`]: trait.DiffSet.html
+pub trait BitFlagSet<T>:
+    FromIterator<T>
+    + Clone
+    + PartialEq
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + BitXor<Output = Self>
+{
+}
+
+impl<T, S> BitFlagSet<T> for S where
+    S: FromIterator<T>
+        + Clone
+        + PartialEq
+        + BitAnd<Output = S>
+        + BitOr<Output = S>
+        + BitXor<Output = S>
+{
+}
+
+/// Looks up the flags of a bitflags-like type by name and vice versa. This is
+/// used by [`parse`] and [`display`].
+///
+/// This trait can be implemented by [`impl_flag_names`], which can also take
+/// the names from `bitflags::Flags` with the `bitflags2` feature.
+///
+/// [`parse`]: fn.parse.html
+/// [`display`]: fn.display.html
+/// [`impl_flag_names`]: macro.impl_flag_names.html
+pub trait FlagNames: Sized {
+    /// Gets the flag named `name`.
+    fn from_flag_name(name: &str) -> Option<Self>;
+
+    /// Gets the name of the `index`-th flag in the declaration order. Returns
+    /// `None` if `index` is out of range. Unnamed flags are represented by
+    /// empty strings.
+    fn nth_flag_name(index: usize) -> Option<&'static str>;
+
+    /// Gets the raw bits of the flag. This is used to accept raw integers in
+    /// place of names (currently only by the `serde` feature). Returns `None`
+    /// by default.
+    fn flag_bits(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// Parses a set of flags at runtime. The input has the same syntax as the
+/// contents of `{...}` in [`flags`] or [`set_array`] (e.g., `"A | B"`,
+/// `"A, B,"`, or `""`), and the flags are looked up by [`FlagNames`].
+///
+/// [`flags`]: macro.flags.html
+/// [`set_array`]: macro.set_array.html
+/// [`FlagNames`]: trait.FlagNames.html
+///
+/// # Examples
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     #[macro_use]
+///     extern crate bitflags;
+///     use flags_macro::{parse, ParseErrorKind};
+///
+///     bitflags! {
+///         struct Test: u32 {
+///             const A = 0b0001;
+///             const B = 0b0010;
+///         }
+///     }
+///
+///     impl_flag_names!(Test => {A, B});
+///
+///     # fn main() {
+///     assert_eq!(parse("A | B"), Ok(Test::A | Test::B));
+///     assert_eq!(parse("B,"), Ok(Test::B));
+///     assert_eq!(parse(""), Ok(Test::empty()));
+///
+///     let error = parse::<Test, Test>("A | C").unwrap_err();
+///     assert_eq!(error.kind(), ParseErrorKind::UnknownName);
+///     assert_eq!(error.token(), "C");
+///     assert_eq!(error.offset(), 4);
+///     # }
+pub fn parse<T, S>(text: &str) -> Result<S, ParseError<'_>>
+where
+    T: FlagNames,
+    S: FromIterator<T>,
+{
+    Parser { text, pos: 0 }
+        .map(|name| {
+            let (offset, name) = name?;
+            T::from_flag_name(name).ok_or(ParseError {
+                kind: ParseErrorKind::UnknownName,
+                token: name,
+                offset,
+            })
+        })
+        .collect()
+}
+
+/// Splits the input of [`parse`] into names and their byte offsets.
+///
+/// [`parse`]: fn.parse.html
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Gets the token at the current position.
+    fn token(&self) -> &'a str {
+        let rest = &self.text[self.pos..];
+        let len = match rest.find(|c| !is_name_char(c)) {
+            Some(0) => rest.chars().next().map_or(0, char::len_utf8),
+            Some(len) => len,
+            None => rest.len(),
+        };
+        &rest[..len]
+    }
+
+    fn error(&mut self, kind: ParseErrorKind) -> ParseError<'a> {
+        let error = ParseError {
+            kind,
+            token: self.token(),
+            offset: self.pos,
+        };
+        // Stop the iteration
+        self.pos = self.text.len();
+        error
+    }
+}
+
+impl<'a> Iterator for Parser<'a> {
+    type Item = Result<(usize, &'a str), ParseError<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.skip_whitespace();
+        if self.pos == self.text.len() {
+            return None;
+        }
+
+        let offset = self.pos;
+        let name = self.token();
+        if !name.starts_with(|c: char| is_name_char(c) && !c.is_numeric()) {
+            return Some(Err(self.error(ParseErrorKind::ExpectedName)));
+        }
+        self.pos += name.len();
+
+        // The separator is optional after the last item
+        self.skip_whitespace();
+        match self.text[self.pos..].chars().next() {
+            None => {}
+            Some('|') | Some(',') => self.pos += 1,
+            Some(_) => return Some(Err(self.error(ParseErrorKind::ExpectedSeparator))),
+        }
+
+        Some(Ok((offset, name)))
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
+}
+
+/// An error returned by [`parse`].
+///
+/// [`parse`]: fn.parse.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError<'a> {
+    kind: ParseErrorKind,
+    token: &'a str,
+    offset: usize,
+}
+
+/// The kind of [`ParseError`].
+///
+/// [`ParseError`]: struct.ParseError.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The name doesn't match any flag.
+    UnknownName,
+    /// A name was expected, but something else was found.
+    ExpectedName,
+    /// `|` or `,` was expected, but something else was found.
+    ExpectedSeparator,
+}
+
+impl<'a> ParseError<'a> {
+    /// Gets the kind of the error.
+    pub fn kind(&self) -> ParseErrorKind {
+        self.kind
+    }
+
+    /// Gets the offending token, which is the unknown name in the case of
+    /// `ParseErrorKind::UnknownName`.
+    pub fn token(&self) -> &'a str {
+        self.token
+    }
+
+    /// Gets the byte offset of the offending token in the input.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<'a> fmt::Display for ParseError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            ParseErrorKind::UnknownName => write!(f, "unknown flag `{}`", self.token)?,
+            ParseErrorKind::ExpectedName => {
+                write!(f, "expected a flag name, found `{}`", self.token)?
+            }
+            ParseErrorKind::ExpectedSeparator => {
+                write!(f, "expected `|` or `,`, found `{}`", self.token)?
+            }
+        }
+        write!(f, " at byte {}", self.offset)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> __std::error::Error for ParseError<'a> {}
+
+/// Formats a set of flags in the syntax of [`flags`] (e.g.,
+/// `Flags::{A | B}`) using [`FlagNames`]. The returned value implements both
+/// `Display` and `Debug`.
+///
+/// Composite flags are preferred over their constituents if they are declared
+/// earlier. The bits not covered by any named flags are omitted unless
+/// [`FlagsDisplay::unknown_bits_as_hex`] is used.
+///
+/// [`flags`]: macro.flags.html
+/// [`FlagNames`]: trait.FlagNames.html
+/// [`FlagsDisplay::unknown_bits_as_hex`]: struct.FlagsDisplay.html#method.unknown_bits_as_hex
+///
+/// # Examples
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     #[macro_use]
+///     extern crate bitflags;
+///     use flags_macro::{display, Separator};
+///
+///     bitflags! {
+///         struct Test: u32 {
+///             const A = 0b0001;
+///             const B = 0b0010;
+///         }
+///     }
+///
+///     impl_flag_names!(Test => {A, B});
+///
+///     # fn main() {
+///     let set = flags![Test::{A | B}];
+///     assert_eq!(display(&set).to_string(), "Test::{A | B}");
+///     assert_eq!(format!("{:?}", display(&flags![Test::{}])), "Test::{}");
+///     assert_eq!(
+///         display(&set).separator(Separator::Comma).prefix("my::Test").to_string(),
+///         "my::Test::{A, B}",
+///     );
+///     assert_eq!(display(&set).no_prefix().to_string(), "A | B");
+///
+///     let set = unsafe { Test::from_bits_unchecked(0x81) };
+///     assert_eq!(display(&set).unknown_bits_as_hex().to_string(), "Test::{A | 0x80}");
+///     # }
+pub fn display<T, S>(set: &S) -> FlagsDisplay<'_, T, S>
+where
+    T: FlagNames,
+    S: BitFlagSet<T>,
+{
+    FlagsDisplay {
+        set,
+        prefix: Prefix::TypeName,
+        separator: Separator::Pipe,
+        fmt_unknown_bits: None,
+        _phantom: PhantomData,
+    }
+}
+
+/// Formats a set of flags. Created by [`display`].
+///
+/// [`display`]: fn.display.html
+pub struct FlagsDisplay<'a, T, S: 'a> {
+    set: &'a S,
+    prefix: Prefix<'a>,
+    separator: Separator,
+    fmt_unknown_bits: Option<fn(&S, &mut fmt::Formatter) -> fmt::Result>,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+#[derive(Clone, Copy)]
+enum Prefix<'a> {
+    TypeName,
+    Custom(&'a str),
+    None,
+}
+
+impl<'a> Prefix<'a> {
+    /// Writes `prefix::{`.
+    fn write_start<T>(self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Prefix::TypeName => {
+                let name = core::any::type_name::<T>();
+                let name = name.split('<').next().unwrap_or(name);
+                write!(f, "{}::{{", name.rsplit("::").next().unwrap_or(name))
+            }
+            Prefix::Custom(prefix) => write!(f, "{}::{{", prefix),
+            Prefix::None => Ok(()),
+        }
+    }
+
+    /// Writes `}`.
+    fn write_end(self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Prefix::None => Ok(()),
+            _ => f.write_str("}"),
+        }
+    }
+}
+
+/// The separator style used by [`FlagsDisplay`].
+///
+/// [`FlagsDisplay`]: struct.FlagsDisplay.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Separator {
+    /// `A | B`
+    Pipe,
+    /// `A, B`
+    Comma,
+}
+
+impl<'a, T, S> FlagsDisplay<'a, T, S> {
+    /// Sets the separator style. Defaults to `Separator::Pipe`.
+    pub fn separator(self, separator: Separator) -> Self {
+        FlagsDisplay { separator, ..self }
+    }
+
+    /// Uses `prefix` instead of the name of `T` (without the module path) as
+    /// the path prefix.
+    pub fn prefix(self, prefix: &'a str) -> Self {
+        FlagsDisplay {
+            prefix: Prefix::Custom(prefix),
+            ..self
+        }
+    }
+
+    /// Omits the path prefix and the braces, producing the syntax accepted by
+    /// [`parse`].
+    ///
+    /// [`parse`]: fn.parse.html
+    pub fn no_prefix(self) -> Self {
+        FlagsDisplay {
+            prefix: Prefix::None,
+            ..self
+        }
+    }
+
+    /// Renders the bits not covered by any named flags as a hexadecimal
+    /// number (e.g., `Flags::{A | 0x80}`).
+    pub fn unknown_bits_as_hex(self) -> Self
+    where
+        S: fmt::LowerHex,
+    {
+        FlagsDisplay {
+            fmt_unknown_bits: Some(|bits, f| write!(f, "{:#x}", bits)),
+            ..self
+        }
+    }
+}
+
+impl<'a, T, S> fmt::Display for FlagsDisplay<'a, T, S>
+where
+    T: FlagNames,
+    S: BitFlagSet<T>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let empty: S = S::from_iter(empty());
+        let separator = match self.separator {
+            Separator::Pipe => " | ",
+            Separator::Comma => ", ",
+        };
+
+        self.prefix.write_start::<T>(f)?;
+
+        let mut first = true;
+        let unknown = for_each_flag_name(self.set, |name| {
+            if !first {
+                f.write_str(separator)?;
+            }
+            first = false;
+            f.write_str(name)
+        })?;
+
+        if let Some(fmt_unknown_bits) = self.fmt_unknown_bits {
+            if unknown != empty {
+                if !first {
+                    f.write_str(separator)?;
+                }
+                fmt_unknown_bits(&unknown, f)?;
+            }
+        }
+
+        self.prefix.write_end(f)
+    }
+}
+
+/// Calls `f` with the names of the flags contained in `set` (preferring the
+/// ones declared earlier) and returns the bits not covered by them.
+fn for_each_flag_name<T, S, E>(
+    set: &S,
+    mut f: impl FnMut(&'static str) -> Result<(), E>,
+) -> Result<S, E>
+where
+    T: FlagNames,
+    S: BitFlagSet<T>,
+{
+    let empty: S = S::from_iter(empty());
+    let mut covered = empty.clone();
+    let names = (0..).map_while(T::nth_flag_name);
+    for name in names.filter(|name| !name.is_empty()) {
+        let flag = match T::from_flag_name(name) {
+            Some(flag) => S::from_iter(Some(flag)),
+            None => continue,
+        };
+        let is_new = flag.clone() & covered.clone() != flag;
+        if flag != empty && set.clone() & flag.clone() == flag && is_new {
+            f(name)?;
+            covered = covered | flag;
+        }
+    }
+    Ok(set.clone() ^ covered)
+}
+
+impl<'a, T, S> fmt::Debug for FlagsDisplay<'a, T, S>
+where
+    T: FlagNames,
+    S: BitFlagSet<T>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Provides the set operations needed by [`diff`] for sets of flags of type
+/// `T`.
+///
+/// This trait has a blanket implementation for bitflags-like types, i.e.,
+/// any `S` such that `T: BitOr<Output = S>` and `S` implements
+/// `FromIterator<T>`, `BitAnd`, `BitOr`, and `BitXor` (all with
+/// `Output = S`). [`impl_default_set`] implements it for the chosen
+/// collection type.
+///
+/// [`diff`]: fn.diff.html
+/// [`impl_default_set`]: macro.impl_default_set.html
+pub trait DiffSet<T>: FromIterator<T> {
+    /// Returns the values in `self` but not in `other`.
+    fn without(&self, other: &Self) -> Self;
+
+    /// Returns the values in both `self` and `other`.
+    fn common(&self, other: &Self) -> Self;
+
+    /// If `self` contains `flag` and `covered` doesn't contain all of it, adds
+    /// `flag` to `covered` and returns `true`. This is used to enumerate the
+    /// names of the flags in `self`, so that composite flags declared earlier
+    /// are preferred over their constituents like [`display`] does.
+    ///
+    /// [`display`]: fn.display.html
+    fn cover_flag(&self, flag: T, covered: &mut Self) -> bool;
+}
+
+impl<T, S> DiffSet<T> for S
+where
+    T: BitOr<Output = S>,
+    S: BitFlagSet<T>,
+{
+    fn without(&self, other: &Self) -> Self {
+        self.clone() ^ self.common(other)
+    }
+
+    fn common(&self, other: &Self) -> Self {
+        self.clone() & other.clone()
+    }
+
+    fn cover_flag(&self, flag: T, covered: &mut Self) -> bool {
+        let flag = S::from_iter(Some(flag));
+        let is_new = flag.clone() & covered.clone() != flag;
+        if flag != S::from_iter(empty()) && self.clone() & flag.clone() == flag && is_new {
+            *covered = covered.clone() | flag;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Compares two sets of flags, e.g., for logging changes of permissions. The
+/// returned value provides the added, removed, and unchanged flags, and the
+/// names of the changes through [`FlagsDiff::changes`] (using
+/// [`FlagNames`]).
+///
+/// The changes are also formatted by `Display` and `Debug` in the syntax of
+/// [`update_flags`] (e.g., `Flags::{+A | -B}`), which can be customized in
+/// the same way as [`display`]. The bits not covered by any named flags are
+/// omitted.
+///
+/// [`FlagsDiff::changes`]: struct.FlagsDiff.html#method.changes
+/// [`FlagNames`]: trait.FlagNames.html
+/// [`update_flags`]: macro.update_flags.html
+/// [`display`]: fn.display.html
+///
+/// # Examples
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     #[macro_use]
+///     extern crate bitflags;
+///     use flags_macro::{diff, Change, Separator};
+///
+///     bitflags! {
+///         struct Perm: u32 {
+///             const Read = 0b0001;
+///             const Write = 0b0010;
+///             const Admin = 0b0100;
+///         }
+///     }
+///
+///     impl_flag_names!(Perm => {Read, Write, Admin});
+///
+///     # fn main() {
+///     let old = flags![Perm::{Read, Admin}];
+///     let new = flags![Perm::{Read, Write}];
+///     let changes = diff(&old, &new);
+///
+///     assert_eq!(*changes.added(), Perm::Write);
+///     assert_eq!(*changes.removed(), Perm::Admin);
+///     assert_eq!(*changes.unchanged(), Perm::Read);
+///     assert_eq!(
+///         changes.changes().collect::<Vec<_>>(),
+///         [Change::Added("Write"), Change::Removed("Admin")],
+///     );
+///
+///     assert_eq!(changes.to_string(), "Perm::{+Write | -Admin}");
+///     assert_eq!(
+///         format!(
+///             "permissions changed: {}",
+///             changes.separator(Separator::Comma).no_prefix(),
+///         ),
+///         "permissions changed: +Write, -Admin",
+///     );
+///     assert!(diff(&old, &old).is_empty());
+///     # }
+pub fn diff<T, S>(old: &S, new: &S) -> FlagsDiff<'static, T, S>
+where
+    S: DiffSet<T>,
+{
+    FlagsDiff {
+        added: new.without(old),
+        removed: old.without(new),
+        unchanged: old.common(new),
+        prefix: Prefix::TypeName,
+        separator: Separator::Pipe,
+        _phantom: PhantomData,
+    }
+}
+
+/// The difference between two sets of flags. Created by [`diff`].
+///
+/// [`diff`]: fn.diff.html
+pub struct FlagsDiff<'a, T, S> {
+    added: S,
+    removed: S,
+    unchanged: S,
+    prefix: Prefix<'a>,
+    separator: Separator,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+/// A change of a named flag. Produced by [`FlagsDiff::changes`].
+///
+/// [`FlagsDiff::changes`]: struct.FlagsDiff.html#method.changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Change {
+    /// The flag was added. Formatted as `+name`.
+    Added(&'static str),
+    /// The flag was removed. Formatted as `-name`.
+    Removed(&'static str),
+}
+
+impl Change {
+    /// Gets the name of the flag.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Change::Added(name) | Change::Removed(name) => name,
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Change::Added(name) => write!(f, "+{}", name),
+            Change::Removed(name) => write!(f, "-{}", name),
+        }
+    }
+}
+
+impl<'a, T, S> FlagsDiff<'a, T, S> {
+    /// Gets the flags contained only in the new set.
+    pub fn added(&self) -> &S {
+        &self.added
+    }
+
+    /// Gets the flags contained only in the old set.
+    pub fn removed(&self) -> &S {
+        &self.removed
+    }
+
+    /// Gets the flags contained in both sets.
+    pub fn unchanged(&self) -> &S {
+        &self.unchanged
+    }
+
+    /// Checks if the sets are equal.
+    pub fn is_empty(&self) -> bool
+    where
+        S: FromIterator<T> + PartialEq,
+    {
+        let empty = S::from_iter(empty());
+        self.added == empty && self.removed == empty
+    }
+
+    /// Iterates over the changes of the named flags in the declaration order.
+    pub fn changes(&self) -> Changes<'_, T, S>
+    where
+        T: FlagNames,
+        S: DiffSet<T>,
+    {
+        Changes {
+            added: &self.added,
+            removed: &self.removed,
+            covered_added: S::from_iter(empty()),
+            covered_removed: S::from_iter(empty()),
+            index: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Sets the separator style. Defaults to `Separator::Pipe`.
+    pub fn separator(self, separator: Separator) -> Self {
+        FlagsDiff { separator, ..self }
+    }
+
+    /// Uses `prefix` instead of the name of `T` (without the module path) as
+    /// the path prefix.
+    pub fn prefix<'b>(self, prefix: &'b str) -> FlagsDiff<'b, T, S> {
+        FlagsDiff {
+            added: self.added,
+            removed: self.removed,
+            unchanged: self.unchanged,
+            prefix: Prefix::Custom(prefix),
+            separator: self.separator,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Omits the path prefix and the braces (e.g., `+A | -B`).
+    pub fn no_prefix(self) -> Self {
+        FlagsDiff {
+            prefix: Prefix::None,
+            ..self
+        }
+    }
+}
+
+impl<'a, T, S> fmt::Display for FlagsDiff<'a, T, S>
+where
+    T: FlagNames,
+    S: DiffSet<T>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let separator = match self.separator {
+            Separator::Pipe => " | ",
+            Separator::Comma => ", ",
+        };
+
+        self.prefix.write_start::<T>(f)?;
+        for (i, change) in self.changes().enumerate() {
+            if i > 0 {
+                f.write_str(separator)?;
+            }
+            write!(f, "{}", change)?;
+        }
+        self.prefix.write_end(f)
+    }
+}
+
+impl<'a, T, S> fmt::Debug for FlagsDiff<'a, T, S>
+where
+    T: FlagNames,
+    S: DiffSet<T>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// An iterator over the changes of the named flags. Created by
+/// [`FlagsDiff::changes`].
+///
+/// [`FlagsDiff::changes`]: struct.FlagsDiff.html#method.changes
+pub struct Changes<'a, T, S: 'a> {
+    added: &'a S,
+    removed: &'a S,
+    covered_added: S,
+    covered_removed: S,
+    index: usize,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<'a, T, S> Iterator for Changes<'a, T, S>
+where
+    T: FlagNames,
+    S: DiffSet<T>,
+{
+    type Item = Change;
+
+    fn next(&mut self) -> Option<Change> {
+        while let Some(name) = T::nth_flag_name(self.index) {
+            self.index += 1;
+            if name.is_empty() {
+                continue;
+            }
+            // `T` isn't necessarily `Clone`, so the flag is looked up twice
+            if let Some(flag) = T::from_flag_name(name) {
+                if self.added.cover_flag(flag, &mut self.covered_added) {
+                    return Some(Change::Added(name));
+                }
+            }
+            if let Some(flag) = T::from_flag_name(name) {
+                if self.removed.cover_flag(flag, &mut self.covered_removed) {
+                    return Some(Change::Removed(name));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Helpers for types implementing [`bitflags::Flags`] (`bitflags` 2.x).
+///
+/// Requires the `bitflags2` feature.
+///
+/// [`bitflags::Flags`]: https://docs.rs/bitflags/2/bitflags/trait.Flags.html
+#[cfg(feature = "bitflags2")]
+pub mod bitflags2 {
+    use bitflags2_crate::Flags;
+
+    /// Constructs a set from the names of its flags, which are looked up by
+    /// `Flags::from_name`. Returns the first unknown name on failure.
+    ///
+    /// # Examples
+    ///
+    ///     # extern crate flags_macro;
+    ///     # #[macro_use]
+    ///     # extern crate bitflags2;
+    ///     # fn main() {
+    ///     bitflags! {
+    ///         #[derive(Debug, PartialEq)]
+    ///         struct Test: u32 {
+    ///             const A = 0b0001;
+    ///             const B = 0b0010;
+    ///         }
+    ///     }
+    ///
+    ///     use flags_macro::bitflags2::from_names;
+    ///     assert_eq!(from_names(["A", "B"].iter().cloned()), Ok(Test::A | Test::B));
+    ///     assert_eq!(from_names::<Test, _>(["A", "C"].iter().cloned()), Err("C"));
+    ///     # }
+    pub fn from_names<'a, T, I>(names: I) -> Result<T, &'a str>
+    where
+        T: Flags,
+        I: IntoIterator<Item = &'a str>,
+    {
+        names.into_iter().try_fold(T::empty(), |set, name| {
+            T::from_name(name).map(|flag| set.union(flag)).ok_or(name)
+        })
+    }
+}
+
+/// Serializes sets of flags as sequences of flag names.
+///
+/// Requires the `serde` feature. The flags are looked up by [`FlagNames`].
+/// Deserialization accepts a sequence of names, a string in the syntax of
+/// [`parse`] (e.g., `"A | B"`), or a raw integer (if
+/// `FlagNames::flag_bits` is implemented).
+///
+/// This module can be used with `#[serde(with = "flags_macro::serde")]`, or
+/// `serialize_with`/`deserialize_with` where the element type can't be
+/// inferred from the set type (e.g.,
+/// `"flags_macro::serde::serialize::<Animal, _, _>"`). [`Named`] is a newtype
+/// wrapper doing the same.
+///
+/// [`FlagNames`]: ../trait.FlagNames.html
+/// [`parse`]: ../fn.parse.html
+/// [`Named`]: struct.Named.html
+///
+/// # Examples
+///
+///     # #[macro_use]
+///     # extern crate flags_macro;
+///     #[macro_use]
+///     extern crate bitflags;
+///     #[macro_use]
+///     extern crate serde_derive;
+///     extern crate serde_json;
+///
+///     bitflags! {
+///         struct Test: u32 {
+///             const A = 0b0001;
+///             const B = 0b0010;
+///         }
+///     }
+///
+///     impl_flag_names!(Test => {A, B} with |flag| flag.bits());
+///
+///     #[derive(Serialize, Deserialize)]
+///     struct Config {
+///         #[serde(with = "flags_macro::serde")]
+///         test: Test,
+///     }
+///
+///     # fn main() {
+///     let config = Config { test: flags![Test::{A | B}] };
+///     assert_eq!(serde_json::to_string(&config).unwrap(), r#"{"test":["A","B"]}"#);
+///
+///     for input in &[r#"{"test":["A","B"]}"#, r#"{"test":"A | B"}"#, r#"{"test":3}"#] {
+///         let config: Config = serde_json::from_str(input).unwrap();
+///         assert_eq!(config.test, Test::A | Test::B);
+///     }
+///     # }
+#[cfg(feature = "serde")]
+pub mod serde {
+    use super::{parse, DiffSet, FlagNames};
+    use core::{
+        fmt,
+        iter::{empty, FromIterator},
+        marker::PhantomData,
+        ops::{Deref, DerefMut},
+    };
+    use serde_crate::{
+        de::{self, Deserializer, SeqAccess, Visitor},
+        ser::{Error as _, SerializeSeq, Serializer},
+        Deserialize, Serialize,
+    };
+
+    /// Serializes `set` as a sequence of flag names. Fails if `set` contains
+    /// bits not covered by any named flags.
+    ///
+    /// `set` can be any set type implementing [`DiffSet`], which includes
+    /// bitflags-like types and the collection types chosen by
+    /// [`impl_default_set`].
+    ///
+    /// [`DiffSet`]: ../trait.DiffSet.html
+    /// [`impl_default_set`]: ../macro.impl_default_set.html
+    pub fn serialize<T, S, Z>(set: &S, serializer: Z) -> Result<Z::Ok, Z::Error>
+    where
+        T: FlagNames,
+        S: DiffSet<T> + PartialEq,
+        Z: Serializer,
+    {
+        let mut len = 0;
+        let unknown = for_each_flag_name::<T, S, ()>(set, |_| {
+            len += 1;
+            Ok(())
+        })
+        .unwrap_or_else(|()| unreachable!());
+        if unknown != S::from_iter(empty()) {
+            return Err(Z::Error::custom(
+                "the set contains bits not covered by any named flags",
+            ));
+        }
+
+        let mut seq = serializer.serialize_seq(Some(len))?;
+        for_each_flag_name::<T, S, _>(set, |name| seq.serialize_element(name))?;
+        seq.end()
+    }
+
+    /// Calls `f` with the names of the flags contained in `set` (preferring the
+    /// ones declared earlier) and returns the values not covered by them.
+    fn for_each_flag_name<T, S, E>(
+        set: &S,
+        mut f: impl FnMut(&'static str) -> Result<(), E>,
+    ) -> Result<S, E>
+    where
+        T: FlagNames,
+        S: DiffSet<T>,
+    {
+        let mut covered = S::from_iter(empty());
+        let names = (0..).map_while(T::nth_flag_name);
+        for name in names.filter(|name| !name.is_empty()) {
+            if let Some(flag) = T::from_flag_name(name) {
+                if set.cover_flag(flag, &mut covered) {
+                    f(name)?;
+                }
+            }
+        }
+        Ok(set.without(&covered))
+    }
+
+    /// Deserializes a set from a sequence of flag names, a string in the
+    /// syntax of [`parse`](../fn.parse.html), or a raw integer.
+    pub fn deserialize<'de, T, S, D>(deserializer: D) -> Result<S, D::Error>
+    where
+        T: FlagNames,
+        S: FromIterator<T>,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(SetVisitor(PhantomData))
+    }
+
+    struct SetVisitor<T, S>(PhantomData<fn() -> (T, S)>);
+
+    impl<'de, T, S> Visitor<'de> for SetVisitor<T, S>
+    where
+        T: FlagNames,
+        S: FromIterator<T>,
+    {
+        type Value = S;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a sequence of flag names, a string, or an integer")
+        }
+
+        fn visit_str<E: de::Error>(self, text: &str) -> Result<S, E> {
+            parse(text).map_err(E::custom)
+        }
+
+        fn visit_u64<E: de::Error>(self, bits: u64) -> Result<S, E> {
+            let mut covered = 0;
+            let mut flags = (0..)
+                .map_while(T::nth_flag_name)
+                .filter_map(T::from_flag_name)
+                .map(|flag| flag.flag_bits().map(|flag_bits| (flag, flag_bits)))
+                .peekable();
+            if let Some(None) = flags.peek() {
+                return Err(E::custom("the flags don't provide raw bits"));
+            }
+            let set = flags
+                .flatten()
+                .filter(|&(_, flag_bits)| flag_bits != 0 && bits & flag_bits == flag_bits)
+                .map(|(flag, flag_bits)| {
+                    covered |= flag_bits;
+                    flag
+                })
+                .collect();
+            if covered != bits {
+                return Err(E::custom(format_args!(
+                    "unknown bits {:#x}",
+                    bits & !covered
+                )));
+            }
+            Ok(set)
+        }
+
+        fn visit_i64<E: de::Error>(self, bits: i64) -> Result<S, E> {
+            if bits < 0 {
+                return Err(E::invalid_value(de::Unexpected::Signed(bits), &self));
+            }
+            self.visit_u64(bits as u64)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<S, A::Error> {
+            let mut error = None;
+            let set = core::iter::from_fn(|| match seq.next_element::<Flag<T>>() {
+                Ok(flag) => flag.map(|Flag(flag)| flag),
+                Err(e) => {
+                    error = Some(e);
+                    None
+                }
+            })
+            .collect();
+            match error {
+                Some(e) => Err(e),
+                None => Ok(set),
+            }
+        }
+    }
+
+    /// A flag deserialized from its name.
+    struct Flag<T>(T);
+
+    impl<'de, T: FlagNames> Deserialize<'de> for Flag<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_str(FlagVisitor(PhantomData))
+        }
+    }
+
+    struct FlagVisitor<T>(PhantomData<fn() -> T>);
+
+    impl<'de, T: FlagNames> Visitor<'de> for FlagVisitor<T> {
+        type Value = Flag<T>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a flag name")
+        }
+
+        fn visit_str<E: de::Error>(self, name: &str) -> Result<Flag<T>, E> {
+            T::from_flag_name(name)
+                .map(Flag)
+                .ok_or_else(|| E::custom(format_args!("unknown flag `{}`", name)))
+        }
+    }
+
+    /// A wrapper of a set of flags serialized by [`serialize`] and
+    /// deserialized by [`deserialize`]. `T` is the element type.
+    ///
+    /// [`serialize`]: fn.serialize.html
+    /// [`deserialize`]: fn.deserialize.html
+    pub struct Named<S, T = S> {
+        set: S,
+        _phantom: PhantomData<fn() -> T>,
+    }
+
+    impl<S, T> Named<S, T> {
+        pub fn new(set: S) -> Self {
+            Named {
+                set,
+                _phantom: PhantomData,
+            }
+        }
+
+        pub fn into_inner(self) -> S {
+            self.set
+        }
+    }
+
+    impl<S, T> From<S> for Named<S, T> {
+        fn from(set: S) -> Self {
+            Named::new(set)
+        }
+    }
+
+    impl<S, T> Deref for Named<S, T> {
+        type Target = S;
+        fn deref(&self) -> &S {
+            &self.set
+        }
+    }
+
+    impl<S, T> DerefMut for Named<S, T> {
+        fn deref_mut(&mut self) -> &mut S {
+            &mut self.set
+        }
+    }
+
+    impl<S: Clone, T> Clone for Named<S, T> {
+        fn clone(&self) -> Self {
+            Named::new(self.set.clone())
+        }
+    }
+
+    impl<S: Copy, T> Copy for Named<S, T> {}
+
+    impl<S: PartialEq, T> PartialEq for Named<S, T> {
+        fn eq(&self, other: &Self) -> bool {
+            self.set == other.set
+        }
+    }
+
+    impl<S: Eq, T> Eq for Named<S, T> {}
+
+    impl<S: fmt::Debug, T> fmt::Debug for Named<S, T> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.debug_tuple("Named").field(&self.set).finish()
+        }
+    }
+
+    impl<S, T> Serialize for Named<S, T>
+    where
+        T: FlagNames,
+        S: DiffSet<T> + PartialEq,
+    {
+        fn serialize<Z: Serializer>(&self, serializer: Z) -> Result<Z::Ok, Z::Error> {
+            serialize::<T, S, Z>(&self.set, serializer)
+        }
+    }
+
+    impl<'de, S, T> Deserialize<'de> for Named<S, T>
+    where
+        T: FlagNames,
+        S: FromIterator<T>,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserialize::<T, S, D>(deserializer).map(Named::new)
+        }
+    }
+}
+
+#[doc(hidden)]
+pub mod __private {
+    use super::{BitOrDefaultSet, BitOrExtendDefaultSet, DefaultSet, SetOps, UpdateSet};
+    #[cfg(feature = "bitflags2")]
+    use bitflags2_crate::Flags;
+    #[cfg(feature = "bitflags2")]
+    use core::convert::TryInto;
+    use core::{
+        fmt,
+        iter::{empty, FromIterator},
+        marker::PhantomData,
+        ops::{BitAnd, BitOr, BitXor, Not, Sub},
+    };
+
+    /// Chooses between `DefaultSet`, `bitflags::Flags`, `BitOrDefaultSet`, and
+    /// `BitOrExtendDefaultSet` by autoref-based specialization.
+    /// `(&&&&Select::<T>::new()).kit()` resolves to the first applicable one
+    /// of `SelectDefaultSet::kit`, `SelectFlags::kit`,
+    /// `SelectBitOrDefaultSet::kit`, and `SelectBitOrExtendDefaultSet::kit`.
+    /// `SelectFlags` is implemented only with the `bitflags2` feature.
+    pub struct Select<T>(PhantomData<T>);
+
+    impl<T> Select<T> {
+        #[allow(clippy::new_without_default)]
+        pub fn new() -> Self {
+            Select(PhantomData)
+        }
+    }
+
+    pub trait SelectDefaultSet {
+        type Kit;
+        fn kit(&self) -> Self::Kit;
+    }
+
+    impl<T: DefaultSet> SelectDefaultSet for &&&Select<T> {
+        type Kit = DefaultSetKit<T>;
+        fn kit(&self) -> Self::Kit {
+            DefaultSetKit(PhantomData)
+        }
+    }
+
+    pub trait SelectFlags {
+        type Kit;
+        fn kit(&self) -> Self::Kit;
+    }
+
+    #[cfg(feature = "bitflags2")]
+    impl<T: Flags> SelectFlags for &&Select<T> {
+        type Kit = FlagsKit<T>;
+        fn kit(&self) -> Self::Kit {
+            FlagsKit(PhantomData)
+        }
+    }
+
+    pub trait SelectBitOrDefaultSet {
+        type Kit;
+        fn kit(&self) -> Self::Kit;
+    }
+
+    impl<T: BitOrDefaultSet> SelectBitOrDefaultSet for &Select<T> {
+        type Kit = BitOrKit<T>;
+        fn kit(&self) -> Self::Kit {
+            BitOrKit(PhantomData)
+        }
+    }
+
+    pub trait SelectBitOrExtendDefaultSet {
+        type Kit;
+        fn kit(&self) -> Self::Kit;
+    }
+
+    impl<T: BitOrExtendDefaultSet> SelectBitOrExtendDefaultSet for Select<T> {
+        type Kit = BitOrExtendKit<T>;
+        fn kit(&self) -> Self::Kit {
+            BitOrExtendKit(PhantomData)
+        }
+    }
+
+    /// Implements the methods of `has_all!` and the like, and `update_flags!`
+    /// for a kit by delegating to `SetOps` and `UpdateSet`.
+    macro_rules! impl_delegating_kit_methods {
+        ($kit:ident) => {
+            impl<T> $kit<T> {
+                pub fn set_contains_all<S: SetOps<T>>(self, set: &S, items: &[T]) -> bool {
+                    set.contains_all(items)
+                }
+
+                pub fn set_intersects<S: SetOps<T>>(self, set: &S, items: &[T]) -> bool {
+                    set.intersects(items)
+                }
+
+                pub fn set_update<S: UpdateSet<T>>(
+                    self,
+                    set: &mut S,
+                    insert: &[T],
+                    remove: &[T],
+                    toggle: &[T],
+                ) {
+                    set.update(insert, remove, toggle)
+                }
+            }
+        };
+    }
+
+    /// Implements `bit(n)` and `bits(expr)` of `flags!` for a kit. The raw
+    /// bits are converted by `from_bits` of the set type, and undeclared bits
+    /// cause a panic with every kit, so that which kit is chosen (e.g., by
+    /// the `bitflags2` feature) doesn't change the result.
+    macro_rules! impl_raw_bits_kit_method {
+        ($kit:ident) => {
+            impl<T> $kit<T> {
+                #[track_caller]
+                pub fn set_from_raw_bits<B, S, R>(
+                    self,
+                    bits: B,
+                    from_bits: impl FnOnce(B) -> R,
+                ) -> S
+                where
+                    B: Copy + fmt::LowerHex,
+                    R: FromBitsResult<S>,
+                {
+                    match from_bits(bits).into_set() {
+                        Some(set) => set,
+                        None => panic!(
+                            "raw bits {:#x} include bits not declared by `{}`",
+                            bits,
+                            core::any::type_name::<S>(),
+                        ),
+                    }
+                }
+            }
+        };
+    }
+
+    impl_raw_bits_kit_method!(DefaultSetKit);
+    #[cfg(feature = "bitflags2")]
+    impl_raw_bits_kit_method!(FlagsKit);
+    impl_raw_bits_kit_method!(BitOrKit);
+    impl_raw_bits_kit_method!(BitOrExtendKit);
+
+    /// Gives the set type whose `from_bits` converts `bit(n)` and
+    /// `bits(expr)` of `flags!`. Types without bit values (e.g., the ones
+    /// using `impl_default_set!`) don't implement this, and thus don't
+    /// support raw bits.
+    pub trait SupportsRawBits {
+        type Set;
+    }
+
+    impl<T: BitOr> SupportsRawBits for T {
+        type Set = T::Output;
+    }
+
+    /// The return value of `from_bits` of a set type, which is an `Option`
+    /// (e.g., `bitflags`) or a `Result` (e.g., `enumflags2`).
+    pub trait FromBitsResult<S> {
+        fn into_set(self) -> Option<S>;
+    }
+
+    impl<S> FromBitsResult<S> for Option<S> {
+        fn into_set(self) -> Option<S> {
+            self
+        }
+    }
+
+    impl<S, E> FromBitsResult<S> for Result<S, E> {
+        fn into_set(self) -> Option<S> {
+            self.ok()
+        }
+    }
+
+    pub struct DefaultSetKit<T>(PhantomData<T>);
+
+    impl<T> Clone for DefaultSetKit<T> {
+        fn clone(&self) -> Self {
+            *self
+        }
+    }
+
+    impl<T> Copy for DefaultSetKit<T> {}
+
+    impl_delegating_kit_methods!(DefaultSetKit);
+
+    impl<T: DefaultSet> DefaultSetKit<T> {
+        pub fn set_from_iter(self, iter: impl IntoIterator<Item = T>) -> T::Set {
+            T::set_from_iter(iter)
+        }
+
+        pub fn set_empty(self) -> T::Set {
+            T::set_empty()
+        }
+
+        pub fn set_all(self) -> T::Set
+        where
+            T::Set: Not<Output = T::Set>,
+        {
+            T::set_all()
+        }
+
+        pub fn set_complement_from_iter(self, iter: impl IntoIterator<Item = T>) -> T::Set
+        where
+            T::Set: Not<Output = T::Set>,
+        {
+            T::set_complement_from_iter(iter)
+        }
+
+        pub fn set_union(self, set: T::Set, other: T::Set) -> T::Set
+        where
+            T::Set: Extend<T> + IntoIterator<Item = T>,
+        {
+            T::set_union(set, other)
+        }
+    }
+
+    #[cfg(feature = "bitflags2")]
+    pub struct FlagsKit<T>(PhantomData<T>);
+
+    #[cfg(feature = "bitflags2")]
+    impl<T> Clone for FlagsKit<T> {
+        fn clone(&self) -> Self {
+            *self
+        }
+    }
+
+    #[cfg(feature = "bitflags2")]
+    impl<T> Copy for FlagsKit<T> {}
+
+    #[cfg(feature = "bitflags2")]
+    impl<T: Flags> FlagsKit<T> {
+        pub fn set_from_iter(self, iter: impl IntoIterator<Item = T>) -> T {
+            iter.into_iter().fold(T::empty(), T::union)
+        }
+
+        pub fn set_empty(self) -> T {
+            T::empty()
+        }
+
+        pub fn set_all(self) -> T {
+            T::all()
+        }
+
+        pub fn set_complement_from_iter(self, iter: impl IntoIterator<Item = T>) -> T {
+            self.set_from_iter(iter).complement()
+        }
+
+        pub fn set_union(self, set: T, other: T) -> T {
+            set.union(other)
+        }
+    }
+
+    #[cfg(feature = "bitflags2")]
+    impl_delegating_kit_methods!(FlagsKit);
+
+    pub struct BitOrKit<T>(PhantomData<T>);
+
+    impl<T> Clone for BitOrKit<T> {
+        fn clone(&self) -> Self {
+            *self
+        }
+    }
+
+    impl<T> Copy for BitOrKit<T> {}
+
+    impl<T: BitOrDefaultSet> BitOrKit<T> {
+        pub fn set_from_iter(self, iter: impl IntoIterator<Item = T>) -> T::Set {
+            T::Set::from_iter(iter)
+        }
+
+        pub fn set_empty(self) -> T::Set {
+            self.set_from_iter(empty())
+        }
+
+        pub fn set_all(self) -> T::Set
+        where
+            T::Set: Not<Output = T::Set>,
+        {
+            !self.set_empty()
+        }
+
+        pub fn set_complement_from_iter(self, iter: impl IntoIterator<Item = T>) -> T::Set
+        where
+            T::Set: Not<Output = T::Set>,
+        {
+            !self.set_from_iter(iter)
+        }
+
+        pub fn set_union(self, set: T::Set, other: T::Set) -> T::Set
+        where
+            T::Set: BitOr<Output = T::Set>,
+        {
+            set | other
+        }
+    }
+
+    impl_delegating_kit_methods!(BitOrKit);
+
+    pub struct BitOrExtendKit<T>(PhantomData<T>);
+
+    impl<T> Clone for BitOrExtendKit<T> {
+        fn clone(&self) -> Self {
+            *self
+        }
+    }
+
+    impl<T> Copy for BitOrExtendKit<T> {}
+
+    impl<T: BitOrExtendDefaultSet> BitOrExtendKit<T> {
+        pub fn set_from_iter(self, iter: impl IntoIterator<Item = T>) -> T::Set {
+            let mut set = T::Set::default();
+            set.extend(iter);
+            set
+        }
+
+        pub fn set_empty(self) -> T::Set {
+            T::Set::default()
+        }
+
+        pub fn set_all(self) -> T::Set
+        where
+            T::Set: Not<Output = T::Set> + IntoIterator<Item = T>,
+        {
+            self.set_complement_from_iter(empty())
+        }
+
+        // `Not` may leave undeclared bits set (e.g. `flagset`), so the
+        // complement is re-collected from its items.
+        pub fn set_complement_from_iter(self, iter: impl IntoIterator<Item = T>) -> T::Set
+        where
+            T::Set: Not<Output = T::Set> + IntoIterator<Item = T>,
+        {
+            let complement = !BitOrExtendKit(PhantomData).set_from_iter(iter);
+            self.set_from_iter(complement)
+        }
+
+        pub fn set_union(self, set: T::Set, other: T::Set) -> T::Set
+        where
+            T::Set: BitOr<Output = T::Set>,
+        {
+            set | other
+        }
+
+        // The set type doesn't implement `FromIterator` (e.g. `flagset`), so
+        // the blanket implementations of `SetOps` and `UpdateSet` don't apply
+        // and the masks are collected by `Extend` instead.
+        pub fn set_contains_all(self, set: &T::Set, items: &[T]) -> bool
+        where
+            T: Copy,
+            T::Set: BitAnd<Output = T::Set> + Copy + PartialEq,
+        {
+            let mask = self.set_from_iter(items.iter().cloned());
+            (*set & mask) == mask
+        }
+
+        pub fn set_intersects(self, set: &T::Set, items: &[T]) -> bool
+        where
+            T: Copy,
+            T::Set: BitAnd<Output = T::Set> + Copy + PartialEq,
+        {
+            (*set & self.set_from_iter(items.iter().cloned())) != self.set_empty()
+        }
+
+        pub fn set_update(self, set: &mut T::Set, insert: &[T], remove: &[T], toggle: &[T])
+        where
+            T: Copy,
+            T::Set: BitOr<Output = T::Set>
+                + BitAnd<Output = T::Set>
+                + BitXor<Output = T::Set>
+                + Not<Output = T::Set>
+                + Copy,
+        {
+            let mask = |items: &[T]| self.set_from_iter(items.iter().cloned());
+            *set = ((*set | mask(insert)) & !mask(remove)) ^ mask(toggle);
+        }
+    }
+
+    /// Fails the compilation of a `*_strict` macro if any nested item paths
+    /// appear more than once. Each path is given as the error message naming
+    /// it (``duplicate item `inner::Item` ``) because a panic in a constant
+    /// can't format the message.
+    pub const fn assert_unique_paths(messages: &[&str]) {
+        let mut i = 0;
+        while i < messages.len() {
+            let mut j = i + 1;
+            while j < messages.len() {
+                if str_eq(messages[i], messages[j]) {
+                    panic!("{}", messages[j]);
+                }
+                j += 1;
+            }
+            i += 1;
+        }
+    }
+
+    const fn str_eq(a: &str, b: &str) -> bool {
+        let (a, b) = (a.as_bytes(), b.as_bytes());
+        if a.len() != b.len() {
+            return false;
+        }
+        let mut i = 0;
+        while i < a.len() {
+            if a[i] != b[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    /// Implements `FlagNames` for `impl_flag_names!(T as bitflags2)`.
+    #[cfg(feature = "bitflags2")]
+    pub fn flags_from_name<T: Flags>(name: &str) -> Option<T> {
+        T::from_name(name)
+    }
+
+    #[cfg(feature = "bitflags2")]
+    pub fn flags_nth_name<T: Flags>(index: usize) -> Option<&'static str> {
+        T::FLAGS.get(index).map(|flag| flag.name())
+    }
+
+    #[cfg(feature = "bitflags2")]
+    pub fn flags_bits<T: Flags>(flag: &T) -> Option<u64>
+    where
+        T::Bits: TryInto<u64>,
+    {
+        flag.bits().try_into().ok()
+    }
+
+    /// Converts `bit(n)` of `flags!` into the integer type of the bits, with a
+    /// check that `n` is within its width (a plain `1 << n` would silently
+    /// wrap in release builds).
+    pub trait Bit {
+        fn bit(n: u32) -> Self;
+    }
+
+    macro_rules! impl_bit {
+        ($($int:ident)*) => {$(
+            impl Bit for $int {
+                #[track_caller]
+                fn bit(n: u32) -> Self {
+                    assert!(
+                        n < $int::BITS,
+                        "`bit({})` is out of range for `{}`",
+                        n,
+                        stringify!($int),
+                    );
+                    1 << n
+                }
+            }
+        )*};
+    }
+
+    impl_bit!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+
+    /// An operand of a set expression (`flags![T::{A} & !T::{B}]`). The
+    /// operators are implemented by the kit `K` through the following traits
+    /// so that they work for every kind of set type.
+    pub struct SetExpr<K, S>(pub K, pub S);
+
+    impl<K, S> SetExpr<K, S> {
+        pub fn into_set(self) -> S {
+            self.1
+        }
+    }
+
+    pub trait KitUnion<S>: Copy {
+        fn union(self, set: S, other: S) -> S;
+    }
+
+    pub trait KitIntersection<S>: Copy {
+        fn intersection(self, set: S, other: S) -> S;
+    }
+
+    pub trait KitDifference<S>: Copy {
+        fn difference(self, set: S, other: S) -> S;
+    }
+
+    pub trait KitSymmetricDifference<S>: Copy {
+        fn symmetric_difference(self, set: S, other: S) -> S;
+    }
+
+    pub trait KitComplement<S>: Copy {
+        fn complement(self, set: S) -> S;
+    }
+
+    impl<K: KitUnion<S>, S> BitOr for SetExpr<K, S> {
+        type Output = Self;
+        fn bitor(self, rhs: Self) -> Self {
+            SetExpr(self.0, self.0.union(self.1, rhs.1))
+        }
+    }
+
+    impl<K: KitIntersection<S>, S> BitAnd for SetExpr<K, S> {
+        type Output = Self;
+        fn bitand(self, rhs: Self) -> Self {
+            SetExpr(self.0, self.0.intersection(self.1, rhs.1))
+        }
+    }
+
+    impl<K: KitDifference<S>, S> Sub for SetExpr<K, S> {
+        type Output = Self;
+        fn sub(self, rhs: Self) -> Self {
+            SetExpr(self.0, self.0.difference(self.1, rhs.1))
+        }
+    }
+
+    impl<K: KitSymmetricDifference<S>, S> BitXor for SetExpr<K, S> {
+        type Output = Self;
+        fn bitxor(self, rhs: Self) -> Self {
+            SetExpr(self.0, self.0.symmetric_difference(self.1, rhs.1))
+        }
+    }
+
+    impl<K: KitComplement<S>, S> Not for SetExpr<K, S> {
+        type Output = Self;
+        fn not(self) -> Self {
+            SetExpr(self.0, self.0.complement(self.1))
+        }
+    }
+
+    impl<T: DefaultSet> KitUnion<T::Set> for DefaultSetKit<T>
+    where
+        T::Set: Extend<T> + IntoIterator<Item = T>,
+    {
+        fn union(self, set: T::Set, other: T::Set) -> T::Set {
+            T::set_union(set, other)
+        }
+    }
+
+    impl<T: DefaultSet> KitIntersection<T::Set> for DefaultSetKit<T>
+    where
+        for<'a> &'a T::Set: BitAnd<&'a T::Set, Output = T::Set>,
+    {
+        fn intersection(self, set: T::Set, other: T::Set) -> T::Set {
+            T::set_intersection(set, other)
+        }
+    }
+
+    impl<T: DefaultSet> KitDifference<T::Set> for DefaultSetKit<T>
+    where
+        for<'a> &'a T::Set: Sub<&'a T::Set, Output = T::Set>,
+    {
+        fn difference(self, set: T::Set, other: T::Set) -> T::Set {
+            T::set_difference(set, other)
+        }
+    }
+
+    impl<T: DefaultSet> KitSymmetricDifference<T::Set> for DefaultSetKit<T>
+    where
+        for<'a> &'a T::Set: BitXor<&'a T::Set, Output = T::Set>,
+    {
+        fn symmetric_difference(self, set: T::Set, other: T::Set) -> T::Set {
+            T::set_symmetric_difference(set, other)
+        }
+    }
+
+    impl<T: DefaultSet> KitComplement<T::Set> for DefaultSetKit<T>
+    where
+        T::Set: Not<Output = T::Set>,
+    {
+        fn complement(self, set: T::Set) -> T::Set {
+            T::set_complement(set)
+        }
+    }
+
+    #[cfg(feature = "bitflags2")]
+    impl<T: Flags> KitUnion<T> for FlagsKit<T> {
+        fn union(self, set: T, other: T) -> T {
+            set.union(other)
+        }
+    }
+
+    #[cfg(feature = "bitflags2")]
+    impl<T: Flags> KitIntersection<T> for FlagsKit<T> {
+        fn intersection(self, set: T, other: T) -> T {
+            set.intersection(other)
+        }
+    }
+
+    #[cfg(feature = "bitflags2")]
+    impl<T: Flags> KitDifference<T> for FlagsKit<T> {
+        fn difference(self, set: T, other: T) -> T {
+            set.difference(other)
+        }
+    }
+
+    #[cfg(feature = "bitflags2")]
+    impl<T: Flags> KitSymmetricDifference<T> for FlagsKit<T> {
+        fn symmetric_difference(self, set: T, other: T) -> T {
+            set.symmetric_difference(other)
+        }
+    }
+
+    #[cfg(feature = "bitflags2")]
+    impl<T: Flags> KitComplement<T> for FlagsKit<T> {
+        fn complement(self, set: T) -> T {
+            set.complement()
+        }
+    }
+
+    /// Implements the set operations of a kit whose set type `$set` provides
+    /// them as bitwise operators. The difference is computed as `set & !other`
+    /// because some set types don't implement `Sub`.
+    macro_rules! impl_bitwise_kit_ops {
+        ($kit:ident, $bound:ident) => {
+            impl<T: $bound> KitUnion<T::Set> for $kit<T>
+            where
+                T::Set: BitOr<Output = T::Set>,
+            {
+                fn union(self, set: T::Set, other: T::Set) -> T::Set {
+                    set | other
+                }
+            }
+
+            impl<T: $bound> KitIntersection<T::Set> for $kit<T>
+            where
+                T::Set: BitAnd<Output = T::Set>,
+            {
+                fn intersection(self, set: T::Set, other: T::Set) -> T::Set {
+                    set & other
+                }
+            }
+
+            impl<T: $bound> KitDifference<T::Set> for $kit<T>
+            where
+                T::Set: BitAnd<Output = T::Set> + Not<Output = T::Set>,
+            {
+                fn difference(self, set: T::Set, other: T::Set) -> T::Set {
+                    set & !other
+                }
+            }
+
+            impl<T: $bound> KitSymmetricDifference<T::Set> for $kit<T>
+            where
+                T::Set: BitXor<Output = T::Set>,
+            {
+                fn symmetric_difference(self, set: T::Set, other: T::Set) -> T::Set {
+                    set ^ other
+                }
+            }
+        };
+    }
+
+    impl_bitwise_kit_ops!(BitOrKit, BitOrDefaultSet);
+    impl_bitwise_kit_ops!(BitOrExtendKit, BitOrExtendDefaultSet);
+
+    impl<T: BitOrDefaultSet> KitComplement<T::Set> for BitOrKit<T>
+    where
+        T::Set: Not<Output = T::Set>,
+    {
+        fn complement(self, set: T::Set) -> T::Set {
+            !set
+        }
+    }
+
+    // See `BitOrExtendKit::set_complement_from_iter`
+    impl<T: BitOrExtendDefaultSet> KitComplement<T::Set> for BitOrExtendKit<T>
+    where
+        T::Set: Not<Output = T::Set> + IntoIterator<Item = T>,
+    {
+        fn complement(self, set: T::Set) -> T::Set {
+            self.set_from_iter(!set)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn trailing_separators() {
+        mod values {
+            pub const A: u32 = 1;
+            pub const B: u32 = 2;
+        }
+
+        assert_eq!(set_array![values::{A | B |}], [values::A, values::B]);
+        assert_eq!(set_array![values::{A, B,}], [values::A, values::B]);
+        assert_eq!(const_flags![values::{A | B |} as u32], 3);
+        assert_eq!(const_flags![values::{A, B,} as u32], 3);
+    }
+
+    #[test]
+    fn const_flags_int() {
+        mod values {
+            pub const A: u8 = 0b001;
+            pub const B: u8 = 0b100;
+        }
+
+        const EMPTY: u8 = const_flags![values::{} as u8];
+        const BOTH: u8 = const_flags![values::{A | B} as u8];
+        assert_eq!(EMPTY, 0);
+        assert_eq!(BOTH, 0b101);
+    }
+
+    #[test]
+    fn unique_paths() {
+        ::__private::assert_unique_paths(&["duplicate item `fs::A`", "duplicate item `net::A`"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate item `fs::A`")]
+    fn duplicate_paths() {
+        ::__private::assert_unique_paths(&[
+            "duplicate item `fs::A`",
+            "duplicate item `net::A`",
+            "duplicate item `fs::A`",
+        ]);
     }
 }
@@ -0,0 +1,158 @@
+extern crate bitflags2;
+#[macro_use(flags, const_flags)]
+extern crate flags_macro;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+
+use bitflags2::bitflags;
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Perms: u8 {
+        const READ = 0b001;
+        const WRITE = 0b010;
+        const EXEC = 0b100;
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Open: u8 {
+        const READ = 0b001;
+        const WRITE = 0b010;
+        const _ = !0;
+    }
+}
+
+#[cfg(feature = "bitflags2")]
+flags_macro::impl_flag_names!(Perms as bitflags2, Open as bitflags2);
+
+#[test]
+fn construct() {
+    assert_eq!(flags![Perms::{}], Perms::empty());
+    assert_eq!(flags![Perms::{READ}], Perms::READ);
+    assert_eq!(flags![Perms::{READ | WRITE}], Perms::READ | Perms::WRITE);
+}
+
+#[test]
+fn exclusion() {
+    assert_eq!(flags![Perms::{*}], Perms::all());
+    assert_eq!(flags![Perms::{* - EXEC}], Perms::READ | Perms::WRITE);
+    assert_eq!(flags![Perms::{!READ, !WRITE}], Perms::EXEC);
+}
+
+#[test]
+fn raw_bits() {
+    assert_eq!(flags![Perms::{READ, bit(1)}], Perms::READ | Perms::WRITE);
+
+    // `const _ = !0` retains the undeclared bits
+    assert_eq!(flags![Open::{READ, bit(7), bits(0x40)}].bits(), 0b1100_0001);
+}
+
+#[test]
+#[should_panic(expected = "raw bits 0x80 include bits not declared by")]
+fn raw_bits_undeclared() {
+    let _ = flags![Perms::{bit(7)}];
+}
+
+#[test]
+fn constant() {
+    const RW: Perms = const_flags![Perms::{READ | WRITE}];
+    assert_eq!(RW, Perms::READ | Perms::WRITE);
+}
+
+#[test]
+fn constant_retain() {
+    const RW: Perms = const_flags![Perms::{READ, WRITE} retain];
+    assert_eq!(RW, Perms::READ | Perms::WRITE);
+    assert_eq!(const_flags![Open::{} retain], Open::empty());
+}
+
+#[cfg(feature = "bitflags2")]
+mod flags_trait {
+    use super::{Open, Perms};
+    use bitflags2::Flags;
+    use flags_macro::bitflags2::from_names;
+
+    fn none<T: Flags>() -> T {
+        flags![T::{}]
+    }
+
+    fn everything<T: Flags>() -> T {
+        flags![T::{*}]
+    }
+
+    #[test]
+    fn generic() {
+        assert_eq!(none::<Perms>(), Perms::empty());
+        assert_eq!(everything::<Perms>(), Perms::all());
+        assert_eq!(everything::<Open>().bits(), !0);
+    }
+
+    #[test]
+    fn exclusion() {
+        assert_eq!(flags![Open::{* - READ}].bits(), !0b001);
+        assert_eq!(flags![Perms::{!EXEC}], Perms::READ | Perms::WRITE);
+    }
+
+    fn without<T: Flags + Copy>(set: T, other: T) -> T {
+        flags![T::{..set} - T::{..other}]
+    }
+
+    #[test]
+    fn set_expressions() {
+        assert_eq!(flags![Perms::{READ, WRITE} & !Perms::{WRITE}], Perms::READ);
+        assert_eq!(flags![!Open::{READ}].bits(), !0b001);
+        assert_eq!(
+            without(Perms::all(), Perms::EXEC),
+            Perms::READ | Perms::WRITE
+        );
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(
+            from_names(vec!["READ", "EXEC"]),
+            Ok(Perms::READ | Perms::EXEC)
+        );
+        assert_eq!(from_names::<Perms, _>(vec![]), Ok(Perms::empty()));
+        assert_eq!(from_names::<Perms, _>(vec!["READ", "Write"]), Err("Write"));
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            flags_macro::parse("READ | EXEC"),
+            Ok(Perms::READ | Perms::EXEC)
+        );
+        let error = flags_macro::parse::<Perms, Perms>("READ, Write").unwrap_err();
+        assert_eq!((error.token(), error.offset()), ("Write", 6));
+    }
+
+    #[test]
+    fn display() {
+        let set = Perms::READ | Perms::EXEC;
+        assert_eq!(
+            flags_macro::display(&set).to_string(),
+            "Perms::{READ | EXEC}"
+        );
+        assert_eq!(
+            flags_macro::display(&Open::from_bits_retain(0x83))
+                .unknown_bits_as_hex()
+                .to_string(),
+            "Open::{READ | WRITE | 0x80}"
+        );
+    }
+}
+
+#[cfg(all(feature = "bitflags2", feature = "serde"))]
+#[test]
+fn serde() {
+    use flags_macro::serde::Named;
+
+    let named: Named<Perms> = Named::new(Perms::READ | Perms::EXEC);
+    assert_eq!(serde_json::to_string(&named).unwrap(), r#"["READ","EXEC"]"#);
+
+    let named: Named<Perms> = serde_json::from_str("6").unwrap();
+    assert_eq!(*named, Perms::WRITE | Perms::EXEC);
+}
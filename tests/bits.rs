@@ -0,0 +1,70 @@
+#[macro_use(bits)]
+extern crate flags_macro;
+
+macro_rules! widths {
+    ($($name:ident: $int:ty,)*) => {$(
+        #[test]
+        fn $name() {
+            mod values {
+                pub const A: $int = 0b001;
+                pub const B: $int = 0b010;
+                pub const C: $int = 0b100;
+            }
+
+            const AB: $int = bits![values::{A | B}];
+            static ALL: $int = bits![values::{A, B, C}];
+            const NONE: $int = bits![values::{} as $int];
+            assert_eq!(AB, 0b011);
+            assert_eq!(ALL, 0b111);
+            assert_eq!(NONE, 0);
+
+            let c: $int = bits![values::{C}];
+            assert_eq!(c, values::C);
+        }
+    )*};
+}
+
+widths! {
+    u8_bits: u8,
+    u16_bits: u16,
+    u32_bits: u32,
+    u64_bits: u64,
+    u128_bits: u128,
+    usize_bits: usize,
+    i8_bits: i8,
+    i16_bits: i16,
+    i32_bits: i32,
+    i64_bits: i64,
+    i128_bits: i128,
+    isize_bits: isize,
+}
+
+mod libc {
+    pub const O_RDONLY: i32 = 0;
+    pub const O_CREAT: i32 = 0o100;
+    pub const O_TRUNC: i32 = 0o1000;
+
+    pub mod sock {
+        pub const SOCK_NONBLOCK: i32 = 0o4000;
+        pub const SOCK_CLOEXEC: i32 = 0o2000000;
+    }
+}
+
+#[test]
+fn set_array_syntax() {
+    const OFLAGS: i32 = bits![libc::{O_RDONLY, O_CREAT | O_TRUNC,}];
+    assert_eq!(OFLAGS, 0o1100);
+
+    const SOCK: i32 = bits![libc::{sock::{SOCK_NONBLOCK, SOCK_CLOEXEC}}];
+    assert_eq!(SOCK, 0o2004000);
+
+    const MIXED: i32 = bits![{libc::O_CREAT, libc::sock::SOCK_CLOEXEC} as i32];
+    assert_eq!(MIXED, 0o2000100);
+
+    const GATED: i32 = bits![libc::{O_CREAT, #[cfg(any())] O_TRUNC}];
+    assert_eq!(GATED, libc::O_CREAT);
+
+    // The result type is inferred from the items
+    let mask = bits![libc::{O_TRUNC}];
+    let _: i32 = mask;
+}
@@ -0,0 +1,163 @@
+//! Items gated by `#[cfg]`. `cfg(all())` and `cfg(any())` are always enabled
+//! and disabled, respectively. The items gated by `feature = "alloc"` are
+//! checked in both states by running the tests with and without the feature.
+#[macro_use]
+extern crate bitflags;
+extern crate enumflags;
+#[macro_use]
+extern crate enumflags_derive;
+
+#[macro_use(flags, flags_strict, set_array, set_array_strict)]
+extern crate flags_macro;
+
+#[allow(non_upper_case_globals)]
+mod ponydom {
+    bitflags! {
+        pub struct Flags: u32 {
+            const Winged = 0b001;
+            const Horned = 0b010;
+            const Earth = 0b100;
+        }
+    }
+}
+
+mod zoo {
+    #[derive(EnumFlags, Copy, Clone, PartialEq, Eq, Debug)]
+    #[repr(u8)]
+    pub enum Animal {
+        Cat = 0b001,
+        Dog = 0b010,
+        Pony = 0b100,
+    }
+}
+
+mod values {
+    pub const A: u32 = 1;
+    pub const B: u32 = 2;
+    pub const C: u32 = 3;
+}
+
+use ponydom::Flags;
+
+#[test]
+fn set_array() {
+    assert_eq!(
+        set_array![values::{A, #[cfg(any())] B, C}],
+        [values::A, values::C]
+    );
+    assert_eq!(
+        set_array![values::{#[cfg(all())] A | #[cfg(any())] B}],
+        [values::A]
+    );
+    assert_eq!(set_array![values::{#[cfg(any())] A} as u32], []);
+    assert_eq!(
+        set_array![values::{#[cfg(all())] #[cfg(not(any()))] A, #[cfg(all())] #[cfg(any())] B}],
+        [values::A]
+    );
+    assert_eq!(
+        set_array_strict![values::{A, #[cfg(any())] A, #[cfg(all())] B,}],
+        [values::A, values::B]
+    );
+}
+
+#[test]
+fn set_array_nested_paths() {
+    mod consts {
+        pub(crate) use values as fs;
+        pub(crate) use values as net;
+    }
+    assert_eq!(
+        set_array![consts::{#[cfg(any())] fs::{A, B}, #[cfg(all())] net::{#[cfg(any())] A, C}}],
+        [values::C]
+    );
+    assert_eq!(
+        set_array_strict![consts::{fs::B, #[cfg(any())] fs::{A}, net::{A, #[cfg(any())] A}}],
+        [values::B, values::A]
+    );
+    assert_eq!(
+        set_array_strict![consts::{fs::A, #[cfg(all())] net::A, #[cfg(any())] fs::{A}}],
+        [values::A, values::A]
+    );
+}
+
+#[test]
+fn mixed_prefix() {
+    assert_eq!(
+        flags![{Flags::Winged, #[cfg(any())] Flags::Horned, #[cfg(all())] Flags::Earth} as Flags],
+        Flags::Winged | Flags::Earth
+    );
+    assert_eq!(
+        set_array![{#[cfg(any())] values::{A, B}, values::C} as u32],
+        [values::C]
+    );
+}
+
+#[test]
+fn flags() {
+    assert_eq!(
+        flags![ponydom::Flags::{Winged, #[cfg(any())] Horned}],
+        Flags::Winged
+    );
+    assert_eq!(
+        flags![ponydom::Flags::{#[cfg(all())] Winged | #[cfg(all())] Horned}],
+        Flags::Winged | Flags::Horned
+    );
+    assert_eq!(
+        flags![ponydom::Flags::{#[cfg(any())] Winged}],
+        Flags::empty()
+    );
+    assert_eq!(
+        flags![zoo::Animal::{Cat, #[cfg(any())] Dog, #[cfg(all())] Pony}],
+        zoo::Animal::Cat | zoo::Animal::Pony
+    );
+}
+
+#[test]
+fn exclusion() {
+    assert_eq!(
+        flags![ponydom::Flags::{* - Winged - #[cfg(any())] Horned}],
+        Flags::Horned | Flags::Earth
+    );
+    assert_eq!(
+        flags![ponydom::Flags::{#[cfg(any())] !Winged, !Horned}],
+        Flags::Winged | Flags::Earth
+    );
+    assert_eq!(
+        flags![ponydom::Flags::{!Winged | #[cfg(all())] !Horned}],
+        Flags::Earth
+    );
+}
+
+#[test]
+fn conditional_items() {
+    let yes = true;
+    assert_eq!(
+        flags![ponydom::Flags::{#[cfg(any())] Winged if yes, #[cfg(all())] Horned if yes}],
+        Flags::Horned
+    );
+    assert_eq!(
+        flags![ponydom::Flags::{Earth, #[cfg(any())] Winged if yes, ..Flags::Horned}],
+        Flags::Horned | Flags::Earth
+    );
+}
+
+#[test]
+fn features() {
+    let set = flags![ponydom::Flags::{
+        Winged,
+        #[cfg(feature = "alloc")] Horned,
+        #[cfg(not(feature = "alloc"))] Earth,
+    }];
+    if cfg!(feature = "alloc") {
+        assert_eq!(set, Flags::Winged | Flags::Horned);
+    } else {
+        assert_eq!(set, Flags::Winged | Flags::Earth);
+    }
+
+    // Mutually exclusive items aren't duplicates
+    let set = flags_strict![ponydom::Flags::{
+        #[cfg(feature = "alloc")] Horned,
+        #[cfg(not(feature = "alloc"))] Horned,
+    }];
+    assert_eq!(set, Flags::Horned);
+}
@@ -0,0 +1,141 @@
+#![cfg(feature = "alloc")]
+#[macro_use(
+    flags,
+    has_all,
+    has_any,
+    has_none,
+    impl_default_set,
+    impl_flag_names,
+    update_flags
+)]
+extern crate flags_macro;
+
+use flags_macro::{diff, Change};
+
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Color {
+    Red,
+    Green,
+    Blue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Fetch,
+    Build,
+    Test,
+}
+
+impl_default_set!(Color => BTreeSet, Step => Vec);
+impl_flag_names!(Color => {Red, Green, Blue}, Step => {Fetch, Build, Test});
+
+#[test]
+fn btree_set() {
+    let colors = flags![Color::{Red | Blue}];
+    let expected: BTreeSet<_> = [Color::Red, Color::Blue].iter().cloned().collect();
+    assert_eq!(colors, expected);
+    assert!(flags![Color::{}].is_empty());
+    assert!(!colors.contains(&Color::Green));
+}
+
+#[test]
+fn vec() {
+    let steps = flags![Step::{Fetch, Test, Build}];
+    assert_eq!(steps, vec![Step::Fetch, Step::Test, Step::Build]);
+}
+
+#[test]
+fn containment() {
+    let colors = flags![Color::{Red | Blue}];
+    assert!(has_all!(colors, Color::{Red, Blue}));
+    assert!(!has_all!(colors, Color::{Red, Green}));
+    assert!(has_any!(colors, Color::{Green, Blue}));
+    assert!(has_none!(colors, Color::{Green}));
+
+    let steps = flags![Step::{Fetch}];
+    assert!(has_all!(steps, Step::{}));
+    assert!(!has_any!(steps, Step::{Build | Test}));
+}
+
+#[test]
+fn update() {
+    let mut colors = flags![Color::{Red, Green}];
+    update_flags!(colors, Color::{+Blue, -Red, ^Green});
+    let expected: BTreeSet<_> = [Color::Blue].iter().cloned().collect();
+    assert_eq!(colors, expected);
+
+    let mut steps = flags![Step::{Fetch, Build}];
+    update_flags!(steps, Step::{+Fetch, -Build, ^Test});
+    assert_eq!(steps, vec![Step::Fetch, Step::Test]);
+    update_flags!(steps, Step::{^Fetch});
+    assert_eq!(steps, vec![Step::Test]);
+}
+
+#[test]
+fn changes() {
+    let old = flags![Color::{Red, Green}];
+    let new = flags![Color::{Green, Blue}];
+    let changes = diff(&old, &new);
+    assert_eq!(*changes.added(), flags![Color::{Blue}]);
+    assert_eq!(*changes.removed(), flags![Color::{Red}]);
+    assert_eq!(*changes.unchanged(), flags![Color::{Green}]);
+    assert_eq!(
+        changes.changes().collect::<Vec<_>>(),
+        [Change::Removed("Red"), Change::Added("Blue")]
+    );
+    assert_eq!(changes.to_string(), "Color::{-Red | +Blue}");
+    assert!(diff(&new, &new).is_empty());
+
+    let old = flags![Step::{Build, Fetch}];
+    let new = flags![Step::{Test, Fetch}];
+    assert_eq!(diff(&old, &new).to_string(), "Step::{-Build | +Test}");
+    assert_eq!(*diff(&old, &new).unchanged(), vec![Step::Fetch]);
+}
+
+#[test]
+fn spread() {
+    let warm = flags![Color::{Red}];
+    let colors = flags![Color::{Blue, ..warm}];
+    let expected: BTreeSet<_> = [Color::Red, Color::Blue].iter().cloned().collect();
+    assert_eq!(colors, expected);
+
+    let steps = flags![Step::{Fetch, ..vec![Step::Build]}];
+    assert_eq!(steps, vec![Step::Fetch, Step::Build]);
+}
+
+#[cfg(feature = "std")]
+mod hash_set {
+    use std::collections::HashSet;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum Shape {
+        Circle,
+        Square,
+    }
+
+    impl_default_set!(Shape => HashSet);
+
+    #[test]
+    fn hash_set() {
+        let shapes = flags![Shape::{Circle, Square}];
+        let expected: HashSet<_> = [Shape::Circle, Shape::Square].iter().cloned().collect();
+        assert_eq!(shapes, expected);
+        assert!(has_all!(shapes, Shape::{Square}));
+        assert!(!has_none!(shapes, Shape::{Circle}));
+
+        let changes = flags_macro::diff(&flags![Shape::{Circle}], &shapes);
+        assert_eq!(*changes.added(), flags![Shape::{Square}]);
+        assert!(!changes.is_empty());
+    }
+}
+
+#[test]
+fn set_expressions() {
+    let expected: BTreeSet<_> = [Color::Red].iter().cloned().collect();
+    assert_eq!(flags![Color::{Red, Green} & Color::{Red, Blue}], expected);
+    assert_eq!(flags![Color::{Red, Green} - Color::{Green}], expected);
+    assert_eq!(flags![Color::{Red, Blue} ^ Color::{Blue}], expected);
+    assert_eq!(flags![Color::{Red} | Color::{Red}], expected);
+}
@@ -0,0 +1,59 @@
+#![cfg(feature = "derive")]
+#[macro_use(flags)]
+extern crate flags_macro;
+
+use flags_macro::DefaultSet;
+use std::{iter::FromIterator, ops::BitOr};
+
+/// A set backed by a fixed-size bitmap, standing in for arena-backed sets.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct SmallBitSet(u64);
+
+impl FromIterator<Level> for SmallBitSet {
+    fn from_iter<I: IntoIterator<Item = Level>>(iter: I) -> Self {
+        SmallBitSet(iter.into_iter().fold(0, |bits, x| bits | (1 << x as u32)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, DefaultSet)]
+#[default_set(SmallBitSet)]
+enum Level {
+    Low,
+    Mid,
+    High,
+}
+
+/// Implements `BitOr`, but the derived `DefaultSet` takes precedence.
+#[derive(Debug, Clone, Copy, PartialEq, DefaultSet)]
+#[default_set(Vec<Mode>)]
+pub struct Mode(u8);
+
+impl Mode {
+    pub const A: Self = Mode(1);
+    pub const B: Self = Mode(2);
+}
+
+impl BitOr for Mode {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        Mode(self.0 | rhs.0)
+    }
+}
+
+impl FromIterator<Mode> for Mode {
+    fn from_iter<I: IntoIterator<Item = Self>>(iter: I) -> Self {
+        iter.into_iter().fold(Mode(0), BitOr::bitor)
+    }
+}
+
+#[test]
+fn derived_enum() {
+    assert_eq!(flags![Level::{Low | High}], SmallBitSet(0b101));
+    assert_eq!(flags![Level::{}], SmallBitSet(0));
+    let _ = Level::Mid;
+}
+
+#[test]
+fn derived_over_bitor() {
+    assert_eq!(flags![Mode::{A, B}], vec![Mode::A, Mode::B]);
+}
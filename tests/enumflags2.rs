@@ -0,0 +1,40 @@
+extern crate enumflags2;
+#[macro_use(flags)]
+extern crate flags_macro;
+
+use enumflags2::{bitflags, BitFlags};
+
+#[bitflags]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Perm {
+    Read = 0b001,
+    Write = 0b010,
+    Exec = 0b100,
+}
+
+#[test]
+fn construct() {
+    assert_eq!(flags![Perm::{}], BitFlags::<Perm>::empty());
+    assert_eq!(flags![Perm::{Read}], Perm::Read);
+    assert_eq!(flags![Perm::{Read | Write}], Perm::Read | Perm::Write);
+}
+
+#[test]
+fn exclusion() {
+    assert_eq!(flags![Perm::{*}], BitFlags::<Perm>::all());
+    assert_eq!(flags![Perm::{* - Exec}], Perm::Read | Perm::Write);
+    assert_eq!(flags![Perm::{!Read, !Write}], Perm::Exec);
+}
+
+#[test]
+fn raw_bits() {
+    assert_eq!(flags![Perm::{Read, bit(1)}], Perm::Read | Perm::Write);
+    assert_eq!(flags![Perm::{bits(0b101)}], Perm::Read | Perm::Exec);
+}
+
+#[test]
+#[should_panic(expected = "raw bits 0x8 include bits not declared by")]
+fn raw_bits_undeclared() {
+    let _ = flags![Perm::{bit(3)}];
+}
@@ -0,0 +1,63 @@
+extern crate flagset;
+#[macro_use(flags, has_all, has_any, has_none, update_flags)]
+extern crate flags_macro;
+
+use flagset::FlagSet;
+
+mod perm {
+    flagset::flags! {
+        pub enum Perm: u8 {
+            Read = 0b001,
+            Write = 0b010,
+            Exec = 0b100,
+        }
+    }
+}
+
+use perm::Perm;
+
+#[test]
+fn construct() {
+    assert_eq!(flags![Perm::{}], FlagSet::<Perm>::empty());
+    assert_eq!(flags![Perm::{Read}], Perm::Read);
+    assert_eq!(flags![Perm::{Read | Write}], Perm::Read | Perm::Write);
+}
+
+#[test]
+fn exclusion() {
+    assert_eq!(flags![Perm::{*}], FlagSet::<Perm>::full());
+    assert_eq!(flags![Perm::{* - Exec}], Perm::Read | Perm::Write);
+    assert_eq!(flags![Perm::{!Read, !Write}], Perm::Exec);
+}
+
+#[test]
+fn set_expressions() {
+    assert_eq!(
+        flags![Perm::{Read, Write} & Perm::{Write, Exec}],
+        Perm::Write
+    );
+    assert_eq!(flags![Perm::{Read, Write} - Perm::{Write}], Perm::Read);
+    assert_eq!(flags![Perm::{Read} ^ Perm::{Read, Exec}], Perm::Exec);
+    assert_eq!(flags![!Perm::{Read}], Perm::Write | Perm::Exec);
+}
+
+#[test]
+fn containment() {
+    let value = flags![Perm::{Read, Write}];
+    assert!(has_all!(value, Perm::{Read, Write}));
+    assert!(!has_all!(value, Perm::{Read, Exec}));
+    assert!(has_any!(value, Perm::{Write, Exec}));
+    assert!(has_none!(value, Perm::{Exec}));
+    assert!(has_all!(value, Perm::{}));
+    assert!(!has_any!(value, Perm::{}));
+}
+
+#[test]
+fn update() {
+    let mut value = flags![Perm::{Write, Exec}];
+    update_flags!(value, Perm::{+Read, -Write});
+    assert_eq!(value, Perm::Read | Perm::Exec);
+
+    update_flags!(value, Perm::{^Read | ^Write});
+    assert_eq!(value, Perm::Write | Perm::Exec);
+}
@@ -0,0 +1,168 @@
+#![cfg(feature = "serde")]
+#[macro_use]
+extern crate bitflags;
+extern crate enumflags;
+#[macro_use]
+extern crate enumflags_derive;
+#[macro_use(flags, impl_flag_names)]
+extern crate flags_macro;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+use enumflags::BitFlags;
+use flags_macro::serde::Named;
+
+#[allow(non_upper_case_globals)]
+mod ponydom {
+    bitflags! {
+        pub struct Flags: u32 {
+            const Winged = 0b01;
+            const Horned = 0b10;
+        }
+    }
+}
+
+mod zoo {
+    #[derive(EnumFlags, Copy, Clone, PartialEq, Eq, Debug)]
+    #[repr(u8)]
+    pub enum Animal {
+        Cat = 0b001,
+        Dog = 0b010,
+        Pony = 0b100,
+    }
+}
+
+impl_flag_names!(
+    ponydom::Flags => {Winged, Horned} with |flag| flag.bits(),
+    zoo::Animal => {Cat, Dog, Pony},
+);
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Stable {
+    #[serde(with = "flags_macro::serde")]
+    pony: ponydom::Flags,
+    #[serde(
+        serialize_with = "flags_macro::serde::serialize::<zoo::Animal, _, _>",
+        deserialize_with = "flags_macro::serde::deserialize::<zoo::Animal, _, _>"
+    )]
+    animals: BitFlags<zoo::Animal>,
+}
+
+#[test]
+fn round_trip() {
+    let stable = Stable {
+        pony: flags![ponydom::Flags::{Winged | Horned}],
+        animals: flags![zoo::Animal::{Cat, Pony}],
+    };
+    let json = serde_json::to_string(&stable).unwrap();
+    assert_eq!(
+        json,
+        r#"{"pony":["Winged","Horned"],"animals":["Cat","Pony"]}"#
+    );
+    assert_eq!(serde_json::from_str::<Stable>(&json).unwrap(), stable);
+}
+
+#[test]
+fn input_forms() {
+    let stable: Stable =
+        serde_json::from_str(r#"{"pony":"Winged | Horned","animals":"Dog,"}"#).unwrap();
+    assert_eq!(stable.pony, ponydom::Flags::all());
+    assert_eq!(stable.animals, zoo::Animal::Dog);
+
+    let stable: Stable = serde_json::from_str(r#"{"pony":2,"animals":[]}"#).unwrap();
+    assert_eq!(stable.pony, ponydom::Flags::Horned);
+    assert_eq!(stable.animals, BitFlags::empty());
+}
+
+#[test]
+fn invalid_inputs() {
+    let error = |json| {
+        serde_json::from_str::<Stable>(json)
+            .unwrap_err()
+            .to_string()
+    };
+    assert!(error(r#"{"pony":["Winged","Horse"],"animals":[]}"#).contains("unknown flag `Horse`"));
+    assert!(error(r#"{"pony":"Winged & Horned","animals":[]}"#).contains("found `&` at byte 7"));
+    assert!(error(r#"{"pony":5,"animals":[]}"#).contains("unknown bits 0x4"));
+    assert!(error(r#"{"pony":-1,"animals":[]}"#).contains("invalid value"));
+    assert!(error(r#"{"pony":[],"animals":1}"#).contains("don't provide raw bits"));
+}
+
+#[test]
+fn unknown_bits() {
+    let flags = unsafe { ponydom::Flags::from_bits_unchecked(0b101) };
+    let named: Named<_> = Named::new(flags);
+    assert!(serde_json::to_string(&named).is_err());
+}
+
+#[test]
+fn named() {
+    let named: Named<BitFlags<zoo::Animal>, zoo::Animal> = flags![zoo::Animal::{Dog}].into();
+    assert_eq!(serde_json::to_string(&named).unwrap(), r#"["Dog"]"#);
+
+    let named: Named<ponydom::Flags> = serde_json::from_str(r#""Horned""#).unwrap();
+    assert_eq!(*named, ponydom::Flags::Horned);
+    assert_eq!(named.into_inner(), ponydom::Flags::Horned);
+}
+
+#[cfg(feature = "alloc")]
+mod collections {
+    use flags_macro::serde::Named;
+    use serde_json;
+    use std::collections::BTreeSet;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    enum Color {
+        Red,
+        Green,
+        Blue,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Step {
+        Fetch,
+        Build,
+        Test,
+    }
+
+    flags_macro::impl_default_set!(Color => BTreeSet, Step => Vec);
+    impl_flag_names!(Color => {Red, Green, Blue}, Step => {Fetch, Build, Test});
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Pipeline {
+        #[serde(
+            serialize_with = "flags_macro::serde::serialize::<Color, _, _>",
+            deserialize_with = "flags_macro::serde::deserialize::<Color, _, _>"
+        )]
+        colors: BTreeSet<Color>,
+        #[serde(
+            serialize_with = "flags_macro::serde::serialize::<Step, _, _>",
+            deserialize_with = "flags_macro::serde::deserialize::<Step, _, _>"
+        )]
+        steps: Vec<Step>,
+    }
+
+    #[test]
+    fn round_trip() {
+        let pipeline = Pipeline {
+            colors: flags![Color::{Blue, Red}],
+            steps: flags![Step::{Fetch, Test}],
+        };
+        let json = serde_json::to_string(&pipeline).unwrap();
+        assert_eq!(
+            json,
+            r#"{"colors":["Red","Blue"],"steps":["Fetch","Test"]}"#
+        );
+        assert_eq!(serde_json::from_str::<Pipeline>(&json).unwrap(), pipeline);
+    }
+
+    #[test]
+    fn named() {
+        let named: Named<BTreeSet<Color>, Color> = flags![Color::{Green}].into();
+        assert_eq!(serde_json::to_string(&named).unwrap(), r#"["Green"]"#);
+
+        let named: Named<Vec<Step>, Step> = serde_json::from_str(r#""Build | Test""#).unwrap();
+        assert_eq!(*named, vec![Step::Build, Step::Test]);
+    }
+}
@@ -1,9 +1,29 @@
 #[macro_use]
 extern crate bitflags;
+extern crate enumflags;
+#[macro_use]
+extern crate enumflags_derive;
 
-#[macro_use(flags)]
+#[macro_use(
+    flags,
+    flags_match,
+    flags_strict,
+    has_all,
+    has_any,
+    has_none,
+    const_flags,
+    set_array,
+    set_array_strict,
+    impl_flag_names,
+    update_flags
+)]
 extern crate flags_macro;
 
+use flags_macro::{
+    diff, display, parse, BitOrDefaultSet, Change, DefaultSet, ParseErrorKind, Separator, UpdateSet,
+};
+
+#[allow(non_upper_case_globals)]
 mod ponydom {
     bitflags! {
         pub struct Flags: u32 {
@@ -13,8 +33,670 @@ mod ponydom {
     }
 }
 
+mod zoo {
+    #[derive(EnumFlags, Copy, Clone, PartialEq, Eq, Debug)]
+    #[repr(u8)]
+    pub enum Animal {
+        Cat = 0b001,
+        Dog = 0b010,
+        Pony = 0b100,
+    }
+}
+
+impl_flag_names!(
+    ponydom::Flags => {Winged, Horned},
+    zoo::Animal => {Cat, Dog, Pony},
+);
+
 #[test]
 fn deeper_path() {
     let alicorn = flags![ponydom::Flags::{Winged | Horned}];
     assert_eq!(alicorn, ponydom::Flags::Winged | ponydom::Flags::Horned);
 }
+
+#[test]
+fn const_deeper_path() {
+    const ALICORN: ponydom::Flags = const_flags![ponydom::Flags::{Winged | Horned}];
+    static PEGASUS: ponydom::Flags = const_flags![ponydom::Flags::{Winged}];
+    assert_eq!(ALICORN, ponydom::Flags::Winged | ponydom::Flags::Horned);
+    assert_eq!(PEGASUS, ponydom::Flags::Winged);
+
+    const UNICORN: ponydom::Flags = const_flags![::ponydom::Flags::{Horned}];
+    assert_eq!(UNICORN, ponydom::Flags::Horned);
+}
+
+#[test]
+fn exclusion() {
+    use ponydom::Flags;
+    assert_eq!(flags![ponydom::Flags::{*}], Flags::all());
+    assert_eq!(flags![ponydom::Flags::{* - Winged}], Flags::Horned);
+    assert_eq!(flags![ponydom::Flags::{!Winged}], Flags::Horned);
+    assert_eq!(flags![ponydom::Flags::{!Winged | !Horned}], Flags::empty());
+    assert_eq!(flags![ponydom::Flags::{!Winged, !Horned,}], Flags::empty());
+}
+
+#[test]
+fn exclusion_enumflags() {
+    use zoo::Animal;
+    assert_eq!(flags![zoo::Animal::{*}], enumflags::BitFlags::all());
+    assert_eq!(flags![zoo::Animal::{* - Cat - Dog}], Animal::Pony);
+    assert_eq!(flags![zoo::Animal::{!Cat, !Pony}], Animal::Dog);
+}
+
+mod generic {
+    use std::{iter::FromIterator, marker::PhantomData, ops::BitOr};
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Mask<T>(pub u32, PhantomData<T>);
+
+    impl<T> Mask<T> {
+        pub const A: Self = Mask(0b01, PhantomData);
+        pub const B: Self = Mask(0b10, PhantomData);
+    }
+
+    impl<T> BitOr for Mask<T> {
+        type Output = Self;
+        fn bitor(self, rhs: Self) -> Self {
+            Mask(self.0 | rhs.0, PhantomData)
+        }
+    }
+
+    impl<T> FromIterator<Mask<T>> for Mask<T> {
+        fn from_iter<I: IntoIterator<Item = Self>>(iter: I) -> Self {
+            iter.into_iter().fold(Mask(0, PhantomData), BitOr::bitor)
+        }
+    }
+
+    pub trait HasFlags {
+        type Flags;
+    }
+
+    pub struct Pony;
+
+    impl HasFlags for Pony {
+        type Flags = ::ponydom::Flags;
+    }
+}
+
+mod nested {
+    #[test]
+    fn super_path() {
+        let winged = flags![super::ponydom::Flags::{Winged}];
+        assert_eq!(winged, ::ponydom::Flags::Winged);
+    }
+}
+
+#[test]
+fn path_forms() {
+    use ponydom::Flags;
+    let both = Flags::Winged | Flags::Horned;
+    assert_eq!(flags![::ponydom::Flags::{Winged | Horned}], both);
+    assert_eq!(flags![crate::ponydom::Flags::{Winged | Horned}], both);
+    assert_eq!(flags![self::ponydom::Flags::{Winged | Horned}], both);
+    assert_eq!(
+        flags![<generic::Pony as generic::HasFlags>::Flags::{Winged | Horned}],
+        both
+    );
+    assert_eq!(
+        flags![generic::Mask<u8>::{A | B}],
+        generic::Mask::<u8>::A | generic::Mask::<u8>::B
+    );
+    assert_eq!(flags![generic::Mask::<u8>::{A}], generic::Mask::<u8>::A);
+    assert_eq!(
+        flags![generic::Mask<Option<u8>>::{B}],
+        generic::Mask::<Option<u8>>::B
+    );
+}
+
+#[test]
+fn set_array_path_forms() {
+    assert_eq!(
+        set_array![generic::Mask<u8>::{A, B}],
+        [generic::Mask::<u8>::A, generic::Mask::<u8>::B]
+    );
+    assert_eq!(
+        set_array![::ponydom::Flags::{Horned}],
+        [ponydom::Flags::Horned]
+    );
+}
+
+mod consts {
+    pub mod fs {
+        pub const O_RDONLY: u32 = 0x0;
+        pub const O_CREAT: u32 = 0x40;
+        pub mod ext {
+            pub const O_DIRECT: u32 = 0x4000;
+        }
+    }
+    pub mod net {
+        pub const SOCK_STREAM: u32 = 1;
+        pub const NONE: u32 = 0;
+    }
+    pub const NONE: u32 = 0;
+}
+
+#[test]
+fn set_array_nested_paths() {
+    use consts::{fs, net};
+    assert_eq!(
+        set_array![consts::{fs::O_RDONLY, net::SOCK_STREAM}],
+        [fs::O_RDONLY, net::SOCK_STREAM]
+    );
+    assert_eq!(
+        set_array![consts::{fs::{O_RDONLY | O_CREAT} | NONE | net::{SOCK_STREAM,},}],
+        [fs::O_RDONLY, fs::O_CREAT, consts::NONE, net::SOCK_STREAM]
+    );
+    assert_eq!(
+        set_array![consts::{fs::{ext::{O_DIRECT}, O_CREAT}, NONE}],
+        [fs::ext::O_DIRECT, fs::O_CREAT, consts::NONE]
+    );
+    assert_eq!(set_array![consts::{fs::{}, net::{}} as u32], []);
+    assert_eq!(
+        set_array_strict![consts::{fs::{O_RDONLY, O_CREAT}, net::SOCK_STREAM}],
+        [fs::O_RDONLY, fs::O_CREAT, net::SOCK_STREAM]
+    );
+    assert_eq!(
+        set_array_strict![consts::{fs::{O_RDONLY, ext::O_DIRECT}, NONE, net::NONE}],
+        [fs::O_RDONLY, fs::ext::O_DIRECT, consts::NONE, net::NONE]
+    );
+}
+
+#[allow(non_upper_case_globals)]
+mod pony_parts {
+    pub mod body {
+        pub const Wings: ::ponydom::Flags = ::ponydom::Flags::Winged;
+    }
+    pub mod head {
+        pub const Horn: ::ponydom::Flags = ::ponydom::Flags::Horned;
+    }
+}
+
+#[test]
+fn mixed_prefix() {
+    use ponydom::Flags;
+    assert_eq!(
+        flags![{pony_parts::body::Wings, pony_parts::head::Horn} as ponydom::Flags],
+        Flags::all()
+    );
+    assert_eq!(
+        flags![{pony_parts::{body::Wings | head::{Horn}}} as Flags],
+        Flags::all()
+    );
+    assert_eq!(flags![{ Flags::Horned } as Flags], Flags::Horned);
+    assert_eq!(flags![{} as ponydom::Flags], Flags::empty());
+    assert_eq!(
+        flags_strict![{pony_parts::body::Wings, Flags::Horned,} as Flags],
+        Flags::all()
+    );
+    assert_eq!(
+        flags![{zoo::Animal::Cat, zoo::Animal::Dog} as zoo::Animal],
+        zoo::Animal::Cat | zoo::Animal::Dog
+    );
+
+    assert_eq!(
+        set_array![{pony_parts::head::Horn, Flags::Winged} as Flags],
+        [Flags::Horned, Flags::Winged]
+    );
+    assert_eq!(set_array_strict![{} as Flags], []);
+
+    let alicorn = Flags::all();
+    assert!(has_all!(alicorn, {pony_parts::body::Wings, Flags::Horned} as Flags));
+    assert!(has_any!(Flags::Winged, { pony_parts::body::Wings } as Flags));
+    assert!(has_none!(Flags::Winged, { pony_parts::head::Horn } as Flags));
+}
+
+#[test]
+fn strict() {
+    use ponydom::Flags;
+    assert_eq!(
+        flags_strict![ponydom::Flags::{Winged | Horned}],
+        Flags::all()
+    );
+    assert_eq!(flags_strict![ponydom::Flags::{!Winged}], Flags::Horned);
+    assert_eq!(flags_strict![ponydom::Flags::{* - Horned}], Flags::Winged);
+    assert_eq!(
+        set_array_strict![ponydom::Flags::{Horned, Winged}],
+        [Flags::Horned, Flags::Winged]
+    );
+
+    const HORNED: [Flags; 1] = set_array_strict![ponydom::Flags::{Horned}];
+    assert_eq!(HORNED, [Flags::Horned]);
+}
+
+fn empty_set<T: BitOrDefaultSet>() -> T::Set {
+    flags![T::{}]
+}
+
+fn empty_custom_set<T: DefaultSet>() -> T::Set {
+    flags![T::{}]
+}
+
+#[test]
+fn empty_generic() {
+    assert_eq!(empty_set::<ponydom::Flags>(), ponydom::Flags::empty());
+    assert_eq!(empty_set::<zoo::Animal>(), enumflags::BitFlags::empty());
+    assert_eq!(
+        empty_custom_set::<custom::Perm>(),
+        custom::PermSet::default()
+    );
+}
+
+#[test]
+fn typed_set_array() {
+    const EMPTY: [ponydom::Flags; 0] = set_array![ponydom::Flags::{} as ponydom::Flags];
+    assert_eq!(EMPTY.len(), 0);
+    assert_eq!(
+        set_array![ponydom::Flags::{Winged} as ponydom::Flags],
+        [ponydom::Flags::Winged]
+    );
+    assert_eq!(set_array_strict![zoo::Animal::{} as zoo::Animal], []);
+}
+
+mod custom {
+    use flags_macro::DefaultSet;
+    use std::{iter::FromIterator, ops::BitOr};
+
+    /// A bitflags-like type whose default set type is overridden.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Perm(u8);
+
+    impl Perm {
+        pub const READ: Self = Perm(0);
+        pub const WRITE: Self = Perm(1);
+    }
+
+    impl BitOr for Perm {
+        type Output = Self;
+        fn bitor(self, rhs: Self) -> Self {
+            Perm(self.0 | rhs.0)
+        }
+    }
+
+    impl FromIterator<Perm> for Perm {
+        fn from_iter<I: IntoIterator<Item = Self>>(iter: I) -> Self {
+            iter.into_iter().fold(Perm(0), BitOr::bitor)
+        }
+    }
+
+    /// A set of `Perm` indexed by bit positions.
+    #[derive(Debug, Default, Clone, Copy, PartialEq)]
+    pub struct PermSet(pub u32);
+
+    impl FromIterator<Perm> for PermSet {
+        fn from_iter<I: IntoIterator<Item = Perm>>(iter: I) -> Self {
+            PermSet(iter.into_iter().fold(0, |bits, perm| bits | (1 << perm.0)))
+        }
+    }
+
+    impl DefaultSet for Perm {
+        type Set = PermSet;
+    }
+}
+
+#[test]
+fn overridden_default_set() {
+    assert_eq!(flags![custom::Perm::{READ | WRITE}], custom::PermSet(0b11));
+    assert_eq!(flags![custom::Perm::{WRITE}], custom::PermSet(0b10));
+}
+
+#[test]
+fn parse_names() {
+    use ponydom::Flags;
+    assert_eq!(parse("Winged | Horned"), Ok(Flags::Winged | Flags::Horned));
+    assert_eq!(parse("Winged,Horned,"), Ok(Flags::Winged | Flags::Horned));
+    assert_eq!(parse(" Horned |\t"), Ok(Flags::Horned));
+    assert_eq!(parse("  "), Ok(Flags::empty()));
+
+    let animals: enumflags::BitFlags<_> = parse::<zoo::Animal, _>("Cat, Pony").unwrap();
+    assert_eq!(animals, flags![zoo::Animal::{Cat, Pony}]);
+}
+
+#[test]
+fn parse_errors() {
+    let error = parse::<ponydom::Flags, ponydom::Flags>("Winged | Horse").unwrap_err();
+    assert_eq!(error.kind(), ParseErrorKind::UnknownName);
+    assert_eq!(error.token(), "Horse");
+    assert_eq!(error.offset(), 9);
+    assert_eq!(error.to_string(), "unknown flag `Horse` at byte 9");
+
+    let error = parse::<ponydom::Flags, ponydom::Flags>("Winged || Horned").unwrap_err();
+    assert_eq!(error.kind(), ParseErrorKind::ExpectedName);
+    assert_eq!((error.token(), error.offset()), ("|", 8));
+
+    let error = parse::<ponydom::Flags, ponydom::Flags>("| Winged").unwrap_err();
+    assert_eq!(error.kind(), ParseErrorKind::ExpectedName);
+    assert_eq!((error.token(), error.offset()), ("|", 0));
+
+    let error = parse::<ponydom::Flags, ponydom::Flags>("Winged & Horned").unwrap_err();
+    assert_eq!(error.kind(), ParseErrorKind::ExpectedSeparator);
+    assert_eq!((error.token(), error.offset()), ("&", 7));
+    assert_eq!(
+        error.to_string(),
+        "expected `|` or `,`, found `&` at byte 7"
+    );
+
+    let error = parse::<ponydom::Flags, ponydom::Flags>("Winged Horned").unwrap_err();
+    assert_eq!(error.kind(), ParseErrorKind::ExpectedSeparator);
+    assert_eq!((error.token(), error.offset()), ("Horned", 7));
+}
+
+#[test]
+fn display_names() {
+    use ponydom::Flags;
+    let alicorn = flags![ponydom::Flags::{Winged | Horned}];
+    assert_eq!(display(&alicorn).to_string(), "Flags::{Winged | Horned}");
+    assert_eq!(
+        format!("{:?}", display(&flags![ponydom::Flags::{}])),
+        "Flags::{}"
+    );
+    assert_eq!(
+        display(&alicorn).separator(Separator::Comma).to_string(),
+        "Flags::{Winged, Horned}"
+    );
+    assert_eq!(
+        display(&alicorn).prefix("ponydom::Flags").to_string(),
+        "ponydom::Flags::{Winged | Horned}"
+    );
+
+    let text = display(&alicorn).no_prefix().to_string();
+    assert_eq!(text, "Winged | Horned");
+    assert_eq!(parse::<Flags, Flags>(&text), Ok(alicorn));
+
+    let animals = flags![zoo::Animal::{Dog | Pony}];
+    assert_eq!(
+        display::<zoo::Animal, _>(&animals).to_string(),
+        "Animal::{Dog | Pony}"
+    );
+}
+
+#[test]
+fn changes() {
+    let old = flags![ponydom::Flags::{Winged}];
+    let new = flags![ponydom::Flags::{Horned}];
+    let changes = diff(&old, &new);
+    assert_eq!(
+        changes.changes().collect::<Vec<_>>(),
+        [Change::Removed("Winged"), Change::Added("Horned")]
+    );
+    assert_eq!(
+        changes.changes().map(|c| c.name()).collect::<Vec<_>>(),
+        ["Winged", "Horned"]
+    );
+    assert_eq!(format!("{:?}", changes), "Flags::{-Winged | +Horned}");
+    assert_eq!(
+        changes
+            .separator(Separator::Comma)
+            .prefix("ponydom::Flags")
+            .to_string(),
+        "ponydom::Flags::{-Winged, +Horned}"
+    );
+    assert_eq!(diff(&old, &old).to_string(), "Flags::{}");
+    assert_eq!(*diff(&old, &old).unchanged(), old);
+
+    let old = flags![zoo::Animal::{Cat, Dog}];
+    let new = flags![zoo::Animal::{Dog, Pony}];
+    let changes = diff::<zoo::Animal, _>(&old, &new);
+    assert_eq!(*changes.added(), zoo::Animal::Pony);
+    assert_eq!(*changes.removed(), zoo::Animal::Cat);
+    assert_eq!(*changes.unchanged(), zoo::Animal::Dog);
+    assert_eq!(changes.no_prefix().to_string(), "-Cat | +Pony");
+}
+
+#[test]
+fn changes_composite() {
+    bitflags! {
+        struct Perm: u8 {
+            const RW = 0b011;
+            const R = 0b001;
+            const W = 0b010;
+            const X = 0b100;
+        }
+    }
+    impl_flag_names!(Perm => {RW, R, W, X});
+
+    assert_eq!(diff(&Perm::empty(), &Perm::RW).to_string(), "Perm::{+RW}");
+    assert_eq!(diff(&Perm::R, &Perm::all()).to_string(), "Perm::{+W | +X}");
+    assert_eq!(diff(&Perm::RW, &Perm::X).to_string(), "Perm::{-RW | +X}");
+}
+
+#[test]
+fn display_unknown_bits() {
+    use ponydom::Flags;
+    let flags = unsafe { Flags::from_bits_unchecked(0b1101) };
+    assert_eq!(display(&flags).to_string(), "Flags::{Winged}");
+    assert_eq!(
+        display(&flags).unknown_bits_as_hex().to_string(),
+        "Flags::{Winged | 0xc}"
+    );
+    let unknown = unsafe { Flags::from_bits_unchecked(0x100) };
+    assert_eq!(
+        display(&unknown).unknown_bits_as_hex().to_string(),
+        "Flags::{0x100}"
+    );
+}
+
+#[test]
+fn match_flags() {
+    use ponydom::Flags;
+    fn kind(pony: Flags) -> &'static str {
+        flags_match!(pony, {
+            all ponydom::Flags::{Winged | Horned} => "alicorn",
+            exactly ponydom::Flags::{Winged} => "pegasus",
+            any ponydom::Flags::{Horned} => { "unicorn" }
+            none ponydom::Flags::{*} => "earth pony",
+            _ => unreachable!(),
+        })
+    }
+    assert_eq!(kind(Flags::all()), "alicorn");
+    assert_eq!(kind(Flags::Winged), "pegasus");
+    assert_eq!(kind(Flags::Horned), "unicorn");
+    assert_eq!(kind(Flags::empty()), "earth pony");
+}
+
+#[test]
+fn match_enumflags() {
+    let mut matched = None;
+    let animals = flags![zoo::Animal::{Cat | Dog}];
+    flags_match!(animals, {
+        none zoo::Animal::{Cat} => matched = Some(0),
+        any zoo::Animal::{Pony, Dog} => matched = Some(1),
+    });
+    assert_eq!(matched, Some(1));
+
+    let count = |animals| {
+        flags_match!(animals, {
+            exactly zoo::Animal::{} => 0,
+            all zoo::Animal::{* - Pony} => 2,
+            _ => 1
+        })
+    };
+    assert_eq!(count(flags![zoo::Animal::{}]), 0);
+    assert_eq!(count(animals), 2);
+    assert_eq!(count(flags![zoo::Animal::{Pony}]), 1);
+}
+
+#[test]
+fn containment() {
+    let alicorn = flags![ponydom::Flags::{Winged | Horned}];
+    let pegasus = flags![ponydom::Flags::{Winged}];
+    assert!(has_all!(alicorn, ponydom::Flags::{Winged | Horned}));
+    assert!(!has_all!(pegasus, ponydom::Flags::{Winged | Horned}));
+    assert!(has_any!(pegasus, ponydom::Flags::{Winged, Horned}));
+    assert!(has_none!(pegasus, ponydom::Flags::{Horned}));
+    assert!(!has_none!(alicorn, ponydom::Flags::{Horned}));
+
+    let animals = flags![zoo::Animal::{Cat | Dog}];
+    assert!(has_all!(animals, zoo::Animal::{Cat, Dog}));
+    assert!(!has_all!(animals, zoo::Animal::{Cat, Pony}));
+    assert!(has_any!(animals, zoo::Animal::{Dog | Pony}));
+    assert!(!has_any!(animals, zoo::Animal::{}));
+    assert!(has_none!(animals, zoo::Animal::{Pony}));
+}
+
+#[test]
+fn conditional_items() {
+    use ponydom::Flags;
+    let (yes, no) = (true, false);
+    assert_eq!(flags![ponydom::Flags::{Winged if yes}], Flags::Winged);
+    assert_eq!(flags![ponydom::Flags::{Winged if no}], Flags::empty());
+    assert_eq!(
+        flags![ponydom::Flags::{Winged, Horned if yes && !no,}],
+        Flags::Winged | Flags::Horned
+    );
+    assert_eq!(
+        flags![ponydom::Flags::{Winged if (no | yes) | Horned if no}],
+        Flags::Winged
+    );
+    assert_eq!(
+        flags![ponydom::Flags::{Winged if no || yes, Horned if yes && no}],
+        Flags::Winged
+    );
+    assert_eq!(
+        flags![ponydom::Flags::{Winged if no || (yes && yes != no) | Horned if no || yes == no}],
+        Flags::Winged
+    );
+    assert_eq!(
+        flags_strict![ponydom::Flags::{Winged if yes, Horned if yes}],
+        Flags::all()
+    );
+
+    let count = 2;
+    assert_eq!(
+        flags![zoo::Animal::{Cat, Dog if count > 1, Pony if count > 2}],
+        zoo::Animal::Cat | zoo::Animal::Dog
+    );
+}
+
+#[test]
+fn spread() {
+    use ponydom::Flags;
+    let winged = flags![ponydom::Flags::{Winged}];
+    let horned = flags![ponydom::Flags::{Horned}];
+    assert_eq!(flags![ponydom::Flags::{..winged}], Flags::Winged);
+    assert_eq!(flags![ponydom::Flags::{Horned, ..winged}], Flags::all());
+    assert_eq!(flags![ponydom::Flags::{..winged | ..horned}], Flags::all());
+    assert_eq!(
+        flags![ponydom::Flags::{..Flags::empty(), Winged if false,}],
+        Flags::empty()
+    );
+    assert_eq!(
+        flags_strict![ponydom::Flags::{Winged, ..horned}],
+        Flags::all()
+    );
+
+    let pets = flags![zoo::Animal::{Cat | Dog}];
+    assert_eq!(
+        flags![zoo::Animal::{Pony, ..pets}],
+        zoo::Animal::Cat | zoo::Animal::Dog | zoo::Animal::Pony
+    );
+}
+
+#[test]
+fn set_expressions() {
+    use ponydom::Flags;
+    use zoo::Animal;
+    assert_eq!(
+        flags![ponydom::Flags::{Winged | Horned} & !ponydom::Flags::{Horned}],
+        Flags::Winged
+    );
+    assert_eq!(
+        flags![ponydom::Flags::{*} - ponydom::Flags::{Winged}],
+        Flags::Horned
+    );
+    assert_eq!(flags![!ponydom::Flags::{Winged}], Flags::Horned);
+    assert_eq!(
+        flags![(ponydom::Flags::{Winged} | ponydom::Flags::{Horned}) ^ ponydom::Flags::{Horned}],
+        Flags::Winged
+    );
+
+    // `&` binds tighter than `^`, which binds tighter than `|`
+    assert_eq!(
+        flags![zoo::Animal::{Cat} | zoo::Animal::{Dog, Pony} ^ zoo::Animal::{Pony} & zoo::Animal::{*}],
+        Animal::Cat | Animal::Dog
+    );
+    assert_eq!(
+        flags![!(zoo::Animal::{Cat} | zoo::Animal::{Dog}) - zoo::Animal::{}],
+        Animal::Pony
+    );
+    let pets = flags![zoo::Animal::{Cat | Dog}];
+    assert_eq!(
+        flags![zoo::Animal::{Dog if false, ..pets} & !zoo::Animal::{Cat}],
+        Animal::Dog
+    );
+    assert_eq!(
+        flags_strict![zoo::Animal::{Cat, Dog} - zoo::Animal::{Dog, Pony}],
+        Animal::Cat
+    );
+}
+
+#[test]
+fn update() {
+    use ponydom::Flags;
+    use zoo::Animal;
+    let mut value = flags![ponydom::Flags::{Horned}];
+    update_flags!(value, ponydom::Flags::{+Winged, -Horned});
+    assert_eq!(value, Flags::Winged);
+    update_flags!(value, ponydom::Flags::{^Winged | ^Horned});
+    assert_eq!(value, Flags::Horned);
+    update_flags!(value, ponydom::Flags::{-Horned,});
+    assert_eq!(value, Flags::empty());
+    update_flags!(value, ponydom::Flags::{+Winged |});
+    assert_eq!(value, Flags::Winged);
+    update_flags!(value, ponydom::Flags::{-Winged});
+    update_flags!(value, ponydom::Flags::{});
+    assert_eq!(value, Flags::empty());
+
+    // Removal takes precedence over insertion
+    UpdateSet::update(
+        &mut value,
+        &[Flags::Winged],
+        &[Flags::Winged],
+        &[Flags::Horned],
+    );
+    assert_eq!(value, Flags::Horned);
+
+    let mut animals = [flags![zoo::Animal::{Cat}]];
+    update_flags!(animals[0], zoo::Animal::{-Cat, +Dog, ^Pony, #[cfg(any())] +Cat});
+    assert_eq!(animals[0], Animal::Dog | Animal::Pony);
+}
+
+#[test]
+fn raw_bits() {
+    use ponydom::Flags;
+    use zoo::Animal;
+    assert_eq!(flags![ponydom::Flags::{bit(0)}], Flags::Winged);
+    assert_eq!(flags![ponydom::Flags::{Winged, bits(0b10)}], Flags::all());
+    let n = 1;
+    assert_eq!(
+        flags_strict![ponydom::Flags::{bit(n) | bit(0)}],
+        Flags::all()
+    );
+    assert_eq!(
+        flags![ponydom::Flags::{*} - ponydom::Flags::{bits(Flags::Horned.bits())}],
+        Flags::Winged
+    );
+
+    assert_eq!(
+        flags![zoo::Animal::{Cat, bit(2)}],
+        Animal::Cat | Animal::Pony
+    );
+}
+
+#[test]
+#[should_panic(expected = "raw bits 0x20 include bits not declared by")]
+fn raw_bit_undeclared() {
+    let _ = flags![ponydom::Flags::{Winged, bit(5)}];
+}
+
+#[test]
+#[should_panic(expected = "raw bits 0x300 include bits not declared by")]
+fn raw_bits_undeclared() {
+    let _ = flags![ponydom::Flags::{bits(0x300)}];
+}
+
+#[test]
+#[should_panic(expected = "`bit(8)` is out of range for `u8`")]
+fn raw_bit_out_of_range() {
+    let n = 8;
+    let _ = flags![zoo::Animal::{bit(n)}];
+}